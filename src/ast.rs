@@ -1,8 +1,14 @@
+use crate::dialect::Dialect;
+
 #[derive(Debug)]
 pub enum Expression {
     Or(Box<Expression>, Box<Expression>),
     And(Box<Expression>, Box<Expression>),
     Not(Box<Expression>),
+    Unary {
+        operator: UnaryOperator,
+        expression: Box<Expression>,
+    },
     Binary {
         left: Box<Expression>,
         operator: BinaryOperator,
@@ -13,8 +19,81 @@ pub enum Expression {
     Integer(i64),
     Float(f64),
     Text(String),
+    Blob(Vec<u8>),
     Boolean(bool),
-    Function(String, Vec<Expression>),
+    Null,
+    /// A function call; `distinct` is set for `count(DISTINCT x)`.
+    Function {
+        name: String,
+        arguments: Vec<Expression>,
+        distinct: bool,
+    },
+    /// A row value `(a, b, ...)`; comparisons between rows are lexicographic.
+    Row(Vec<Expression>),
+    /// `expression COLLATE name`, with the name upper-cased.
+    Collate {
+        expression: Box<Expression>,
+        collation: String,
+    },
+    /// A session variable `@name`, set with `SET @name = value`.
+    Variable(String),
+    /// A bind parameter, supplied when the statement is executed.
+    Parameter(Parameter),
+    /// `CURRENT_TIMESTAMP`, `CURRENT_DATE` or `CURRENT_TIME`.
+    CurrentTime(CurrentTime),
+    /// A parenthesized `SELECT` yielding a single value.
+    Subquery(Box<Select>),
+    /// `EXISTS (SELECT ...)`, true if the subquery returns any row.
+    Exists(Box<Select>),
+    /// `CAST(expression AS type)`, with the type name as written.
+    Cast {
+        expression: Box<Expression>,
+        data_type: String,
+    },
+    /// `CASE [operand] WHEN ... THEN ... [ELSE ...] END`
+    Case {
+        operand: Option<Box<Expression>>,
+        branches: Vec<(Expression, Expression)>,
+        else_result: Option<Box<Expression>>,
+    },
+    /// `expression [NOT] BETWEEN low AND high`, bounds included.
+    Between {
+        expression: Box<Expression>,
+        low: Box<Expression>,
+        high: Box<Expression>,
+        negated: bool,
+    },
+    /// `expression [NOT] IN (a, b, ...)`.
+    InList {
+        expression: Box<Expression>,
+        list: Vec<Expression>,
+        negated: bool,
+    },
+    /// `expression [NOT] LIKE pattern [ESCAPE escape]`.
+    Like {
+        expression: Box<Expression>,
+        pattern: Box<Expression>,
+        escape: Option<Box<Expression>>,
+        negated: bool,
+    },
+    /// `expression IS [NOT] NULL`.
+    IsNull {
+        expression: Box<Expression>,
+        negated: bool,
+    },
+    /// `expression [NOT] IN (SELECT ...)`.
+    InSubquery {
+        expression: Box<Expression>,
+        subquery: Box<Select>,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrentTime {
+    Timestamp,
+    Date,
+    Time,
 }
 
 #[derive(Debug)]
@@ -25,48 +104,574 @@ pub enum BinaryOperator {
     LessThanOrEqual,
     GreaterThan,
     GreaterThanOrEqual,
+    /// Null-safe inequality, which is never NULL.
+    IsDistinctFrom,
+    /// Null-safe equality, the negation of `IsDistinctFrom`.
+    IsNotDistinctFrom,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+#[derive(Debug)]
+pub enum UnaryOperator {
+    Plus,
+    Minus,
 }
 
 #[derive(Debug)]
 pub struct Insert {
     pub table: Table,
+    /// Empty when omitted, meaning every column in table order.
     pub columns: Vec<String>,
-    pub values: Option<Vec<Value>>,
+    /// One list of expressions per row in `VALUES (...), (...)`.
+    pub values: Option<Vec<Vec<Expression>>>,
     pub select: Option<Box<Select>>,
+    /// `INSERT INTO t DEFAULT VALUES`: one row of column defaults.
+    pub default_values: bool,
+    /// What to do with rows that violate a uniqueness constraint.
+    pub on_conflict: Option<OnConflict>,
+    /// `RETURNING ...`: values computed from each inserted row.
+    pub returning: Vec<SelectItem>,
 }
 
+/// `ON CONFLICT [(columns)] DO NOTHING | DO UPDATE SET ... [WHERE ...]`
+#[derive(Debug)]
+pub struct OnConflict {
+    /// The constraint's columns; empty means any uniqueness constraint.
+    pub target: Vec<String>,
+    pub action: ConflictAction,
+}
+
+#[derive(Debug)]
+pub enum ConflictAction {
+    /// Skip the conflicting row.
+    DoNothing,
+    /// Update the existing row, reading the new one as `excluded`.
+    DoUpdate {
+        assignments: Vec<Assignment>,
+        where_clause: Option<Expression>,
+    },
+}
+
+/// `column = value` in a SET list.
+#[derive(Debug)]
+pub struct Assignment {
+    pub column: String,
+    pub value: Expression,
+}
+
+/// A join onto the sources before it; joins apply left to right.
 #[derive(Debug)]
 pub struct Join {
-    pub table: Table,
+    pub kind: JoinKind,
+    /// `NATURAL`: equality on every column the two sides have in common.
+    pub natural: bool,
+    pub table: TableReference,
+    /// `ON condition`
     pub condition: Option<Expression>,
+    /// `USING (column, ...)`
+    pub using: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    /// `[INNER] JOIN`
+    Inner,
+    /// `LEFT [OUTER] JOIN`
+    Left,
+    /// `RIGHT [OUTER] JOIN`
+    Right,
+    /// `FULL [OUTER] JOIN`
+    Full,
+    /// `CROSS JOIN` or a comma
+    Cross,
+}
+
+/// `MERGE INTO target USING source ON condition WHEN ...`
+#[derive(Debug)]
+pub struct Merge {
+    pub target: Table,
+    pub source: TableReference,
+    pub condition: Expression,
+    pub clauses: Vec<MergeClause>,
+}
+
+/// One `WHEN [NOT] MATCHED [AND condition] THEN action` branch of a MERGE.
+#[derive(Debug)]
+pub struct MergeClause {
+    pub matched: bool,
+    pub condition: Option<Expression>,
+    pub action: MergeAction,
+}
+
+#[derive(Debug)]
+pub enum MergeAction {
+    Update(Vec<Assignment>),
+    Delete,
+    Insert {
+        columns: Vec<String>,
+        values: Vec<Expression>,
+    },
 }
 
 #[derive(Debug)]
 pub struct Ordering {
     pub expression: Expression,
     pub direction: SortOrder,
+    /// Explicit `NULLS FIRST` / `NULLS LAST`, if given.
+    pub nulls: Option<NullsOrder>,
+}
+
+impl Ordering {
+    /// Where NULLs end up for this key, by SQLite's default.
+    pub fn nulls_order(&self) -> NullsOrder {
+        self.nulls_order_in(Dialect::default())
+    }
+
+    /// Where NULLs end up for this key under `dialect`'s default.
+    pub fn nulls_order_in(&self, dialect: Dialect) -> NullsOrder {
+        let ascending = matches!(self.direction, SortOrder::Ascending);
+        match self.nulls {
+            Some(nulls) => nulls,
+            None if ascending != dialect.nulls_sort_high() => NullsOrder::First,
+            None => NullsOrder::Last,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Query {
     Select(Select),
     Insert(Insert),
+    Truncate(Table),
+    Merge(Merge),
+    Update(Update),
+    Delete(Delete),
+    CreateTable(CreateTable),
+    DropTable(DropTable),
+    AlterTable(AlterTable),
+    CreateIndex(CreateIndex),
+    DropIndex(DropIndex),
+    CreateView(CreateView),
+    CreateTrigger(CreateTrigger),
+    DropView(DropView),
+    /// `REFRESH MATERIALIZED VIEW name`
+    RefreshMaterializedView(String),
+    /// `SET @name = value`
+    SetVariable {
+        name: String,
+        value: Expression,
+    },
+    Grant(Privileges),
+    Revoke(Privileges),
+    /// `BEGIN [DEFERRED | IMMEDIATE | EXCLUSIVE] [TRANSACTION]`
+    Begin(TransactionMode),
+    /// `COMMIT [TRANSACTION]`, or its synonym `END [TRANSACTION]`
+    Commit,
+    /// `ROLLBACK [TRANSACTION] [TO [SAVEPOINT] name]`
+    Rollback {
+        savepoint: Option<String>,
+    },
+    /// `SAVEPOINT name`
+    Savepoint(String),
+    /// `RELEASE [SAVEPOINT] name`
+    Release(String),
+    Pragma(Pragma),
+    /// `EXPLAIN [QUERY PLAN] statement`
+    Explain {
+        query_plan: bool,
+        statement: Box<Query>,
+    },
+    /// `WITH [RECURSIVE] name AS (...), ... statement`
+    With {
+        recursive: bool,
+        ctes: Vec<CommonTableExpression>,
+        body: Box<Query>,
+    },
+}
+
+/// `PRAGMA [schema.]name [= value | (value)]`
+#[derive(Debug)]
+pub struct Pragma {
+    pub schema: Option<String>,
+    pub name: String,
+    /// A bare word such as `WAL` or `ON` is given as `Value::Text`.
+    pub value: Option<Value>,
+}
+
+/// When a transaction started with BEGIN takes its locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionMode {
+    /// Locks are taken when the database is first read or written.
+    #[default]
+    Deferred,
+    /// A write lock is taken at once.
+    Immediate,
+    /// An exclusive lock is taken at once, keeping out readers too.
+    Exclusive,
+}
+
+/// A named subquery in a WITH clause: `name(col1, col2) AS (SELECT ...)`.
+#[derive(Debug)]
+pub struct CommonTableExpression {
+    pub name: String,
+    /// Optional renaming of the subquery's output columns.
+    pub columns: Vec<String>,
+    pub query: Box<Select>,
 }
 
 #[derive(Debug)]
 pub struct Select {
-    pub columns: Vec<Expression>,
-    pub table: Table,
+    /// `SELECT DISTINCT` rather than `SELECT ALL`.
+    pub distinct: bool,
+    pub columns: Vec<SelectItem>,
+    pub table: TableReference,
     pub joins: Vec<Join>,
     pub where_clause: Option<Expression>,
     pub group_by: Option<Vec<Expression>>,
     pub having: Option<Expression>,
+    /// Selects combined with this one by set operators, left to right.
+    pub compound: Vec<(SetOperator, Select)>,
     pub order_by: Option<Vec<Ordering>>,
+    /// The most rows to return.
+    pub limit: Option<Expression>,
+    /// How many rows to skip before returning any.
+    pub offset: Option<Expression>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    /// Rows of either select, without duplicates.
+    Union,
+    /// Rows of either select, keeping duplicates.
+    UnionAll,
+    /// Rows of the left select that the right one also returns.
+    Intersect,
+    /// Rows of the left select that the right one does not return.
+    Except,
+}
+
+impl Select {
+    /// Resolves an ORDER BY key that is an output column number or alias.
+    pub fn resolve_order_key<'a>(&'a self, key: &'a Expression) -> Result<&'a Expression, String> {
+        match key {
+            Expression::Integer(ordinal) => {
+                let index = usize::try_from(*ordinal)
+                    .ok()
+                    .and_then(|ordinal| ordinal.checked_sub(1))
+                    .filter(|&index| index < self.columns.len())
+                    .ok_or_else(|| {
+                        format!(
+                            "ORDER BY term {} is out of range - should be between 1 and {}",
+                            ordinal,
+                            self.columns.len()
+                        )
+                    })?;
+                Ok(&self.columns[index].expression)
+            }
+            Expression::Identifier(name) => Ok(self
+                .columns
+                .iter()
+                .find(|item| item.alias.as_deref() == Some(name.as_str()))
+                .map_or(key, |item| &item.expression)),
+            _ => Ok(key),
+        }
+    }
+}
+
+/// One entry of the SELECT list: `expression [AS alias]`.
+#[derive(Debug)]
+pub struct SelectItem {
+    pub expression: Expression,
+    pub alias: Option<String>,
+}
+
+/// `UPDATE table SET ... [FROM source [JOIN ...]] [WHERE ...]`
+#[derive(Debug)]
+pub struct Update {
+    pub table: Table,
+    pub assignments: Vec<Assignment>,
+    /// Additional rows joined to the target; each match drives one update.
+    pub from: Option<TableReference>,
+    pub joins: Vec<Join>,
+    pub where_clause: Option<Expression>,
+    /// `RETURNING ...`, computed from each updated row.
+    pub returning: Vec<SelectItem>,
+}
+
+/// `DELETE FROM table [USING source [JOIN ...]] [WHERE ...]`
+#[derive(Debug)]
+pub struct Delete {
+    pub table: Table,
+    /// Additional rows joined to the target; matched target rows are deleted.
+    pub using: Option<TableReference>,
+    pub joins: Vec<Join>,
+    pub where_clause: Option<Expression>,
+    /// `RETURNING ...`: values computed from each deleted row.
+    pub returning: Vec<SelectItem>,
+}
+
+/// `CREATE [TEMP | TEMPORARY] TABLE [IF NOT EXISTS] name (column [type], ...)`
+#[derive(Debug)]
+pub struct CreateTable {
+    pub name: String,
+    pub temporary: bool,
+    pub if_not_exists: bool,
+    pub columns: Vec<ColumnDef>,
+}
+
+/// `DROP TABLE [IF EXISTS] name`
+#[derive(Debug)]
+pub struct DropTable {
+    pub name: String,
+    pub if_exists: bool,
+}
+
+/// `ALTER TABLE name operation`
+#[derive(Debug)]
+pub struct AlterTable {
+    pub name: String,
+    pub operation: AlterTableOperation,
+}
+
+#[derive(Debug)]
+pub enum AlterTableOperation {
+    /// `RENAME TO new_name`
+    RenameTable(String),
+    /// `RENAME [COLUMN] old_name TO new_name`
+    RenameColumn { old_name: String, new_name: String },
+    /// `ADD [COLUMN] column_def`
+    AddColumn(ColumnDef),
+    /// `DROP [COLUMN] name`
+    DropColumn(String),
+}
+
+/// `CREATE [TEMP] TRIGGER name timing event ON table ... BEGIN ... END`
+#[derive(Debug)]
+pub struct CreateTrigger {
+    pub name: String,
+    pub temporary: bool,
+    pub if_not_exists: bool,
+    pub timing: TriggerTiming,
+    pub event: TriggerEvent,
+    pub table: String,
+    /// Whether `FOR EACH ROW` was written; SQLite only has row triggers.
+    pub for_each_row: bool,
+    /// The trigger only fires for rows where this holds.
+    pub when: Option<Expression>,
+    /// The statements run each time the trigger fires.
+    pub body: Vec<Query>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TriggerTiming {
+    /// The default when no timing is given, as in SQLite.
+    #[default]
+    Before,
+    After,
+    /// Replaces a change to a view, which cannot be changed directly.
+    InsteadOf,
+}
+
+#[derive(Debug)]
+pub enum TriggerEvent {
+    Insert,
+    /// `UPDATE [OF column, ...]`; with no columns, any update fires it.
+    Update(Vec<String>),
+    Delete,
+}
+
+/// `CREATE [UNIQUE] INDEX [IF NOT EXISTS] name ON table (column, ...)`
+#[derive(Debug)]
+pub struct CreateIndex {
+    pub name: String,
+    pub table: String,
+    pub unique: bool,
+    pub if_not_exists: bool,
+    pub columns: Vec<IndexedColumn>,
+}
+
+/// A column in an index's key, with its sort direction.
+#[derive(Debug)]
+pub struct IndexedColumn {
+    pub name: String,
+    pub direction: SortOrder,
+}
+
+/// `DROP INDEX [IF EXISTS] name`
+#[derive(Debug)]
+pub struct DropIndex {
+    pub name: String,
+    pub if_exists: bool,
+}
+
+/// A column declared in CREATE TABLE.
+#[derive(Debug)]
+pub struct ColumnDef {
+    pub name: String,
+    /// The declared type as written, e.g. `VARCHAR(255)`, if any.
+    pub data_type: Option<String>,
+    /// The constraints following the type, in the order written.
+    pub constraints: Vec<ColumnConstraint>,
+}
+
+#[derive(Debug)]
+pub enum ColumnConstraint {
+    /// `PRIMARY KEY [ASC | DESC] [AUTOINCREMENT]`
+    PrimaryKey {
+        direction: SortOrder,
+        autoincrement: bool,
+    },
+    NotNull,
+    Unique,
+    /// `DEFAULT value`, used when an INSERT does not give the column.
+    Default(Expression),
+    /// `CHECK (condition)`, which every row must satisfy.
+    Check(Expression),
+    /// `COLLATE name`, the column's default collation, upper-cased.
+    Collate(String),
+    /// `REFERENCES table [(column, ...)]`
+    References {
+        table: String,
+        columns: Vec<String>,
+    },
+}
+
+/// `CREATE [TEMP | TEMPORARY] [MATERIALIZED] VIEW [IF NOT EXISTS] name AS SELECT ...`
+#[derive(Debug)]
+pub struct CreateView {
+    pub name: String,
+    /// `CREATE TEMP VIEW`: visible only to this connection.
+    pub temporary: bool,
+    /// `CREATE MATERIALIZED VIEW`: stored until `REFRESH`.
+    pub materialized: bool,
+    pub if_not_exists: bool,
+    /// Optional names for the view's columns, replacing those of the query.
+    pub columns: Vec<String>,
+    pub query: Box<Select>,
+}
+
+/// `DROP [MATERIALIZED] VIEW [IF EXISTS] name`
+#[derive(Debug)]
+pub struct DropView {
+    pub name: String,
+    pub materialized: bool,
+    pub if_exists: bool,
+}
+
+/// The body of a `GRANT` or `REVOKE` statement.
+#[derive(Debug)]
+pub struct Privileges {
+    pub privileges: Vec<Privilege>,
+    pub table: String,
+    pub users: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    /// Schema changes to the table: ALTER TABLE and DROP TABLE.
+    Alter,
+    /// `ALL [PRIVILEGES]`
+    All,
 }
 
 #[derive(Debug)]
 pub struct Table {
+    /// The schema in `schema.table`, such as `main` or `information_schema`.
+    pub schema: Option<String>,
+    pub name: String,
+    pub alias: Option<String>,
+    /// Reads the table as it was at an earlier point: `t AS OF ...`.
+    pub as_of: Option<AsOf>,
+    /// Reads a random sample of the table's rows instead of all of them.
+    pub sample: Option<TableSample>,
+    /// Overrides the planner's choice of index for this table.
+    pub index_hint: Option<IndexHint>,
+}
+
+/// `TABLESAMPLE (n PERCENT)` or `TABLESAMPLE (n ROWS)` after a table.
+#[derive(Debug)]
+pub struct TableSample {
+    pub size: Expression,
+    pub unit: SampleUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleUnit {
+    /// Each row is kept with probability `size / 100`.
+    Percent,
+    /// Exactly `size` rows are kept, or every row if there are fewer.
+    Rows,
+}
+
+/// `INDEXED BY name` or `NOT INDEXED` after a table.
+#[derive(Debug)]
+pub enum IndexHint {
+    /// Look rows up through the named index only.
+    IndexedBy(String),
+    /// Scan the table without using any index.
+    NotIndexed,
+}
+
+/// The point in history a time-travel query reads a table at.
+#[derive(Debug)]
+pub enum AsOf {
+    /// `AS OF TIMESTAMP expr`
+    Timestamp(Expression),
+    /// `AS OF TRANSACTION expr`, a transaction ID
+    Transaction(Expression),
+}
+
+impl Table {
+    /// The name columns are qualified with: the alias, else the table name.
+    pub fn scope_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A source of rows in a FROM or JOIN clause.
+#[derive(Debug)]
+pub enum TableReference {
+    Table(Table),
+    Derived(DerivedTable),
+    Function(TableFunction),
+}
+
+impl TableReference {
+    /// The name columns of this source are qualified with, if it has one.
+    pub fn scope_name(&self) -> Option<&str> {
+        match self {
+            TableReference::Table(table) => Some(table.scope_name()),
+            TableReference::Derived(derived) => derived.alias.as_deref(),
+            TableReference::Function(function) => {
+                Some(function.alias.as_deref().unwrap_or(&function.name))
+            }
+        }
+    }
+}
+
+/// A subquery used as a table: `(SELECT ...) AS sub(col1, col2)`.
+#[derive(Debug)]
+pub struct DerivedTable {
+    pub subquery: Box<Select>,
+    pub alias: Option<String>,
+    /// Optional renaming of the subquery's output columns.
+    pub columns: Vec<String>,
+}
+
+/// A function producing rows, used as a table: `generate_series(1, 100)`.
+#[derive(Debug)]
+pub struct TableFunction {
     pub name: String,
+    pub arguments: Vec<Expression>,
+    pub alias: Option<String>,
 }
 
 #[derive(Debug)]
@@ -75,11 +680,30 @@ pub enum SortOrder {
     Descending,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
 #[derive(Debug)]
 pub enum Value {
     Integer(i64),
     Float(f64),
     Text(String),
+    Blob(Vec<u8>),
     Boolean(bool),
     Null,
+    Parameter(Parameter),
+}
+
+/// A bind parameter, numbered from 1 as in SQLite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Parameter {
+    /// `?`
+    Next,
+    /// `?NNN`
+    Numbered(u32),
+    /// `:name` or `$name`, including the prefix.
+    Named(String),
 }
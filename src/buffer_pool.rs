@@ -2,7 +2,7 @@
 TODO: I thought I had implemented it with the utmost care so that it wouldn't cause a deadlock, but there are some parts that seem to be causing a deadlock when I run the unit tests.
 */
 
-use crate::storage::{NodeType, Page, StorageEngine};
+use crate::storage::{NodeType, Page, StorageEngine, PAGE_SIZE};
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
@@ -17,6 +17,27 @@ pub struct BufferPool {
 struct PoolAndLRU {
     pool: HashMap<u32, Arc<Page>>,
     lru_queue: VecDeque<u32>,
+    /// Bytes the cache tries to stay within; see `set_soft_heap_limit`.
+    soft_heap_limit: Option<usize>,
+}
+
+impl PoolAndLRU {
+    /// Evicts least recently used pages until `reserve` more pages fit
+    /// within both the capacity and the soft heap limit. The limit is soft:
+    /// the cache is never shrunk below the pages being reserved.
+    fn evict(&mut self, capacity: usize, reserve: usize) {
+        let max_pages = match self.soft_heap_limit {
+            Some(limit) => capacity.min(limit / PAGE_SIZE),
+            None => capacity,
+        };
+        while self.pool.len() + reserve > max_pages.max(reserve) {
+            let Some(old_id) = self.lru_queue.pop_back() else {
+                break;
+            };
+            println!("BufferPool - Evicting least recently used page {}.", old_id);
+            self.pool.remove(&old_id);
+        }
+    }
 }
 
 impl BufferPool {
@@ -27,11 +48,33 @@ impl BufferPool {
             pool_and_lru: Mutex::new(PoolAndLRU {
                 pool: HashMap::new(),
                 lru_queue: VecDeque::new(),
+                soft_heap_limit: None,
             }),
             storage: Mutex::new(storage),
         }
     }
 
+    /// Creates a copy-on-write branch with its own empty cache, sharing the
+    /// pages already written to storage. See `StorageEngine::branch`.
+    pub fn branch(&self) -> std::io::Result<Self> {
+        let storage = self.storage.lock().unwrap().branch()?;
+        Ok(BufferPool::new(self.capacity, storage))
+    }
+
+    /// Returns the bytes held by cached pages.
+    pub fn memory_used(&self) -> usize {
+        self.pool_and_lru.lock().unwrap().pool.len() * PAGE_SIZE
+    }
+
+    /// Sets the number of bytes the cache tries to stay within, evicting
+    /// least recently used pages at once if it is already over. `None`
+    /// leaves only the page capacity.
+    pub fn set_soft_heap_limit(&self, limit: Option<usize>) {
+        let mut pool_lru = self.pool_and_lru.lock().unwrap();
+        pool_lru.soft_heap_limit = limit;
+        pool_lru.evict(self.capacity, 0);
+    }
+
     /// Retrieves a page by its ID. If not cached, loads from storage.
     pub fn get_page(&self, page_id: u32) -> std::io::Result<Arc<Page>> {
         println!("BufferPool::get_page - Requested page_id: {}", page_id);
@@ -80,16 +123,8 @@ impl BufferPool {
         {
             let mut pool_lru = self.pool_and_lru.lock().unwrap();
 
-            // Evict least recently used page if capacity is exceeded
-            if pool_lru.pool.len() >= self.capacity {
-                if let Some(old_id) = pool_lru.lru_queue.pop_back() {
-                    println!(
-                        "BufferPool::get_page - Evicting least recently used page {}.",
-                        old_id
-                    );
-                    pool_lru.pool.remove(&old_id);
-                }
-            }
+            // Evict least recently used pages if capacity is exceeded
+            pool_lru.evict(self.capacity, 1);
 
             pool_lru.pool.insert(page_id_new, Arc::clone(&page));
             pool_lru.lru_queue.push_front(page_id_new);
@@ -137,16 +172,8 @@ impl BufferPool {
         );
         // Insert the new page into the pool
 
-        // Evict least recently used page if capacity is exceeded
-        if pool_lru.pool.len() >= self.capacity {
-            if let Some(old_id) = pool_lru.lru_queue.pop_back() {
-                println!(
-                    "BufferPool::allocate_page - Evicting least recently used page {}.",
-                    old_id
-                );
-                pool_lru.pool.remove(&old_id);
-            }
-        }
+        // Evict least recently used pages if capacity is exceeded
+        pool_lru.evict(self.capacity, 1);
 
         pool_lru.pool.insert(page_id_new, Arc::clone(&page));
         pool_lru.lru_queue.push_front(page_id_new);
@@ -154,3 +181,39 @@ impl BufferPool {
         Ok(page)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Tests that the soft heap limit shrinks the cache.
+    #[test]
+    fn test_soft_heap_limit() {
+        let test_db = "test_soft_heap_limit.db";
+        let _ = fs::remove_file(test_db);
+
+        let pool = BufferPool::new(10, StorageEngine::new(test_db).unwrap());
+        for _ in 0..4 {
+            pool.allocate_page(NodeType::Leaf).unwrap();
+        }
+        assert_eq!(pool.memory_used(), 4 * PAGE_SIZE);
+
+        pool.set_soft_heap_limit(Some(2 * PAGE_SIZE));
+        assert_eq!(pool.memory_used(), 2 * PAGE_SIZE);
+        pool.get_page(0).unwrap();
+        assert_eq!(pool.memory_used(), 2 * PAGE_SIZE);
+
+        // The page being loaded is kept even when the limit is below a page
+        pool.set_soft_heap_limit(Some(0));
+        assert_eq!(pool.memory_used(), 0);
+        pool.get_page(1).unwrap();
+        assert_eq!(pool.memory_used(), PAGE_SIZE);
+
+        pool.set_soft_heap_limit(None);
+        pool.get_page(2).unwrap();
+        assert_eq!(pool.memory_used(), 2 * PAGE_SIZE);
+
+        let _ = fs::remove_file(test_db);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::tokens::Keyword;
+
+/// The SQL dialect whose lexical rules the lexer follows, so that scripts
+/// written for other databases can be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// SQLite: identifiers may be quoted with `"`, backticks or `[...]`.
+    #[default]
+    Sqlite,
+    /// Standard SQL: identifiers may be quoted with `"` only.
+    Ansi,
+    /// MySQL: identifiers are quoted with backticks, `#` starts a comment
+    /// that runs to the end of the line, and `\` escapes characters in
+    /// string literals.
+    MySql,
+}
+
+impl Dialect {
+    /// If `c` opens a quoted identifier, returns the character closing it.
+    pub fn identifier_quote(self, c: char) -> Option<char> {
+        match (self, c) {
+            (Dialect::Sqlite | Dialect::Ansi, '"') => Some('"'),
+            (Dialect::Sqlite | Dialect::MySql, '`') => Some('`'),
+            (Dialect::Sqlite, '[') => Some(']'),
+            _ => None,
+        }
+    }
+
+    /// Whether `#` starts a comment.
+    pub fn hash_comments(self) -> bool {
+        self == Dialect::MySql
+    }
+
+    /// Whether `\` escapes the next character in a string literal.
+    pub fn backslash_escapes(self) -> bool {
+        self == Dialect::MySql
+    }
+
+    /// Whether NULLs sort above every other value. SQLite and MySQL sort
+    /// them lowest; standard SQL leaves it to the implementation, and
+    /// PostgreSQL and Oracle sort them highest, which `Ansi` follows.
+    /// In every dialect, NULLs still count as equal to each other for
+    /// DISTINCT and GROUP BY, and an empty string is not NULL.
+    pub fn nulls_sort_high(self) -> bool {
+        self == Dialect::Ansi
+    }
+
+    /// Whether `keyword` is a keyword at all, rather than always an
+    /// identifier. `TEMP` is SQLite's shorthand for `TEMPORARY`.
+    pub fn recognizes(self, keyword: Keyword) -> bool {
+        keyword != Keyword::Temp || self == Dialect::Sqlite
+    }
+}
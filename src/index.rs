@@ -9,6 +9,8 @@ pub const ORDER: usize = 4;
 #[derive(Debug)]
 struct BPlusTreeNode {
     keys: Vec<Key>,
+    /// A leaf's value for each of its keys; empty in internal nodes.
+    values: Vec<Value>,
     children: Vec<Arc<RwLock<BPlusTreeNode>>>,
     is_leaf: bool,
 }
@@ -30,6 +32,7 @@ impl BPlusTree {
         // Initialize the root node as a leaf
         let root_node = Arc::new(RwLock::new(BPlusTreeNode {
             keys: Vec::new(),
+            values: Vec::new(),
             children: Vec::new(),
             is_leaf: true,
         }));
@@ -49,6 +52,7 @@ impl BPlusTree {
             // Tree is empty, create a new leaf node
             let new_leaf = Arc::new(RwLock::new(BPlusTreeNode {
                 keys: vec![key],
+                values: vec![value],
                 children: Vec::new(),
                 is_leaf: true,
             }));
@@ -62,6 +66,7 @@ impl BPlusTree {
             // Create a new root
             let new_root = Arc::new(RwLock::new(BPlusTreeNode {
                 keys: vec![new_key],
+                values: Vec::new(),
                 children: vec![Arc::clone(root_guard.as_ref().unwrap()), new_child],
                 is_leaf: false,
             }));
@@ -81,12 +86,13 @@ impl BPlusTree {
         let mut node_guard = node.write().unwrap();
 
         if node_guard.is_leaf {
-            // Insert the key in the leaf node
-            if node_guard.keys.contains(&key) {
-                return Err("Duplicate key insertion is not allowed".to_string());
-            }
-            node_guard.keys.push(key);
-            node_guard.keys.sort();
+            // Insert the key and its value in the leaf node
+            let pos = match node_guard.keys.binary_search(&key) {
+                Ok(_) => return Err("Duplicate key insertion is not allowed".to_string()),
+                Err(pos) => pos,
+            };
+            node_guard.keys.insert(pos, key);
+            node_guard.values.insert(pos, value);
 
             if node_guard.keys.len() > self.order - 1 {
                 // Split the leaf node
@@ -95,6 +101,7 @@ impl BPlusTree {
 
                 let new_leaf = Arc::new(RwLock::new(BPlusTreeNode {
                     keys: node_guard.keys.split_off(mid),
+                    values: node_guard.values.split_off(mid),
                     children: Vec::new(),
                     is_leaf: true,
                 }));
@@ -135,6 +142,7 @@ impl BPlusTree {
 
                         let new_internal = Arc::new(RwLock::new(BPlusTreeNode {
                             keys: node_guard.keys.split_off(mid + 1),
+                            values: Vec::new(),
                             children: node_guard.children.split_off(mid + 1),
                             is_leaf: false,
                         }));
@@ -148,6 +156,121 @@ impl BPlusTree {
         }
     }
 
+    /// Builds the tree bottom-up from entries sorted by strictly increasing
+    /// key. Leaves are filled completely and each internal level is built from
+    /// the one below it, which avoids the descent and split work of inserting
+    /// keys one at a time. The tree must be empty. Returns the number of keys
+    /// loaded; on error the tree is left unchanged.
+    pub fn bulk_load<I>(&self, entries: I) -> Result<usize, String>
+    where
+        I: IntoIterator<Item = (Key, Value)>,
+    {
+        let mut root_guard = self.root.write().unwrap();
+        if root_guard.as_ref().is_some_and(|root| {
+            let root_guard = root.read().unwrap();
+            !root_guard.is_leaf || !root_guard.keys.is_empty()
+        }) {
+            return Err("Bulk load requires an empty tree".to_string());
+        }
+
+        // Each level holds its nodes paired with the largest key below them
+        let leaf_capacity = self.order - 1;
+        let mut level = Vec::new();
+        let mut keys = Vec::with_capacity(leaf_capacity);
+        let mut values = Vec::with_capacity(leaf_capacity);
+        let mut previous: Option<Key> = None;
+        let mut loaded = 0;
+        for (key, value) in entries {
+            if previous.is_some_and(|previous| previous >= key) {
+                return Err("Bulk load requires keys in strictly increasing order".to_string());
+            }
+            previous = Some(key);
+            keys.push(key);
+            values.push(value);
+            loaded += 1;
+
+            if keys.len() == leaf_capacity {
+                let leaf_keys = std::mem::replace(&mut keys, Vec::with_capacity(leaf_capacity));
+                let leaf_values = std::mem::replace(&mut values, Vec::with_capacity(leaf_capacity));
+                level.push(Self::bulk_node(leaf_keys, leaf_values, Vec::new()));
+            }
+        }
+        if !keys.is_empty() {
+            level.push(Self::bulk_node(keys, values, Vec::new()));
+        }
+
+        // Internal keys are the largest key of each child but the last, since
+        // searches descend into the first child whose key is >= the target
+        while level.len() > 1 {
+            level = level
+                .chunks(self.order)
+                .map(|group| {
+                    let keys = group[..group.len() - 1]
+                        .iter()
+                        .map(|(max, _)| *max)
+                        .collect();
+                    let children = group.iter().map(|(_, child)| Arc::clone(child)).collect();
+                    let (_, node) = Self::bulk_node(keys, Vec::new(), children);
+                    (group[group.len() - 1].0, node)
+                })
+                .collect();
+        }
+
+        if let Some((_, root)) = level.pop() {
+            *root_guard = Some(root);
+        }
+        Ok(loaded)
+    }
+
+    /// Creates a node for `bulk_load`, paired with the largest key below it.
+    /// Nodes without children are leaves.
+    fn bulk_node(
+        keys: Vec<Key>,
+        values: Vec<Value>,
+        children: Vec<Arc<RwLock<BPlusTreeNode>>>,
+    ) -> (Key, Arc<RwLock<BPlusTreeNode>>) {
+        let max = *keys.last().unwrap_or(&Key::MIN);
+        let is_leaf = children.is_empty();
+        let node = Arc::new(RwLock::new(BPlusTreeNode {
+            keys,
+            values,
+            children,
+            is_leaf,
+        }));
+        (max, node)
+    }
+
+    /// Removes every key by replacing the tree with an empty root leaf and
+    /// returns how many keys were removed. The old nodes are dropped as a
+    /// whole instead of being deleted key by key.
+    pub fn clear(&self) -> Result<usize, String> {
+        let mut root_guard = self.root.write().unwrap();
+
+        let removed = match root_guard.as_ref() {
+            Some(root) => Self::count_keys(root),
+            None => 0,
+        };
+
+        *root_guard = Some(Arc::new(RwLock::new(BPlusTreeNode {
+            keys: Vec::new(),
+            values: Vec::new(),
+            children: Vec::new(),
+            is_leaf: true,
+        })));
+
+        Ok(removed)
+    }
+
+    /// Counts the keys stored in the leaves below a node.
+    fn count_keys(node: &Arc<RwLock<BPlusTreeNode>>) -> usize {
+        let node_guard = node.read().unwrap();
+        if node_guard.is_leaf {
+            node_guard.keys.len()
+        } else {
+            node_guard.children.iter().map(Self::count_keys).sum()
+        }
+    }
+
     /// Searches for a value by its key in the B+ Tree.
     pub fn search(&self, key: Key) -> Result<Option<Value>, String> {
         let root_guard = self.root.read().unwrap();
@@ -170,11 +293,7 @@ impl BPlusTree {
         if node_guard.is_leaf {
             // Search in the leaf node
             match node_guard.keys.binary_search(&key) {
-                Ok(_idx) => {
-                    // Assuming values are stored alongside keys. Adjust accordingly.
-                    // Here, for simplicity, returning a dummy Value.
-                    Ok(Some(Value::from(key as u64 * 10)))
-                }
+                Ok(idx) => Ok(Some(node_guard.values[idx])),
                 Err(_) => Ok(None),
             }
         } else {
@@ -248,6 +367,67 @@ mod tests {
         println!("Test completed successfully.");
     }
 
+    /// Tests that clearing the tree reports the removed keys and empties it.
+    #[test]
+    fn test_clear() {
+        let test_db = "test_clear.db";
+        let _ = fs::remove_file(test_db);
+
+        let buffer_pool = Arc::new(BufferPool::new(100, StorageEngine::new(test_db).unwrap()));
+        let tree = BPlusTree::new(Arc::clone(&buffer_pool), ORDER)
+            .expect("Failed to initialize BPlusTree");
+
+        for i in 0..20 {
+            tree.insert(i, Value::from((i * 10) as u64))
+                .expect("Failed to insert key-value pair");
+        }
+
+        assert_eq!(tree.clear().expect("Failed to clear tree"), 20);
+        assert_eq!(tree.search(5).expect("Failed to search for key"), None);
+        assert_eq!(tree.clear().expect("Failed to clear tree"), 0);
+
+        // The cleared tree accepts new keys again
+        tree.insert(5, 50).expect("Failed to insert key-value pair");
+        assert_eq!(tree.clear().expect("Failed to clear tree"), 1);
+
+        let _ = fs::remove_file(test_db);
+    }
+
+    /// Tests building a multi-level tree with bulk_load and searching it.
+    #[test]
+    fn test_bulk_load() {
+        let test_db = "test_bulk_load.db";
+        let _ = fs::remove_file(test_db);
+
+        let buffer_pool = Arc::new(BufferPool::new(100, StorageEngine::new(test_db).unwrap()));
+        let tree = BPlusTree::new(Arc::clone(&buffer_pool), ORDER)
+            .expect("Failed to initialize BPlusTree");
+
+        // Values unrelated to the keys, so each must come from its entry
+        let value = |i: i32| Value::from((1000 - i) as u64 * 7);
+        let loaded = tree
+            .bulk_load((0..1000).map(|i| (i * 2, value(i))))
+            .expect("Failed to bulk load");
+        assert_eq!(loaded, 1000);
+
+        for i in 0..1000 {
+            let result = tree.search(i * 2).expect("Failed to search for key");
+            assert_eq!(result, Some(value(i)));
+            assert_eq!(
+                tree.search(i * 2 + 1).expect("Failed to search for key"),
+                None
+            );
+        }
+
+        // A non-empty tree and unsorted input are both rejected
+        assert!(tree.bulk_load([(5000, 0)]).is_err());
+        assert_eq!(tree.clear().expect("Failed to clear tree"), 1000);
+        assert!(tree.bulk_load([(2, 0), (1, 0)]).is_err());
+        assert_eq!(tree.bulk_load([]).expect("Failed to bulk load"), 0);
+
+        let _ = fs::remove_file(test_db);
+    }
+
     /// Tests multi-threaded insert and search operations.
     #[test]
     fn test_multi_thread_insert_and_search() {
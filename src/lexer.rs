@@ -1,39 +1,69 @@
-use crate::tokens::{is_boolean, is_keyword, Token};
-use std::str::Chars;
+use crate::dialect::Dialect;
+use crate::tokens::{is_boolean, Keyword, Token};
+use std::borrow::Cow;
 
+/// Splits SQL text into tokens. Tokens borrow their text from the input, so
+/// lexing does not allocate; use `Token::into_owned` to keep a token beyond
+/// the input's lifetime.
+#[derive(Clone)]
 pub struct Lexer<'a> {
-    chars: Chars<'a>,
+    input: &'a str,
+    /// Byte offset of `current_char` in `input`.
+    position: usize,
     current_char: Option<char>,
     peek_char: Option<char>,
+    dialect: Dialect,
+    /// Byte offset where the most recently returned token starts.
+    token_start: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        Lexer::with_dialect(input, Dialect::default())
+    }
+
+    /// Creates a lexer that follows the lexical rules of `dialect`.
+    pub fn with_dialect(input: &'a str, dialect: Dialect) -> Self {
         let mut l = Lexer {
-            chars: input.chars(),
+            input,
+            position: 0,
             current_char: None,
             peek_char: None,
+            dialect,
+            token_start: 0,
         };
-        l.read_char();
         l.read_char_peek();
         l
     }
 
     fn read_char(&mut self) {
-        self.current_char = self.chars.next();
+        if let Some(c) = self.current_char {
+            self.position += c.len_utf8();
+        }
+        self.read_char_peek();
     }
 
     fn read_char_peek(&mut self) {
-        self.peek_char = self.chars.clone().next();
+        let mut chars = self.input[self.position..].chars();
+        self.current_char = chars.next();
+        self.peek_char = chars.next();
+    }
+
+    /// The byte range of the most recently returned token in the input.
+    pub fn token_span(&self) -> (usize, usize) {
+        (self.token_start, self.position)
     }
 
-    pub fn next_token(&mut self) -> Option<Token> {
-        self.skip_whitespace();
+    pub fn next_token(&mut self) -> Option<Token<'a>> {
+        self.skip_whitespace_and_comments();
+        self.token_start = self.position;
 
         let token = match self.current_char {
+            Some('x' | 'X') if self.peek_char == Some('\'') => self.read_blob(),
             Some(c) if c.is_alphabetic() => self.read_identifier(),
             Some(c) if c.is_digit(10) => self.read_number(),
             Some('\'') => self.read_string_literal(),
+            Some(c) if self.dialect.identifier_quote(c).is_some() => self.read_quoted_identifier(),
             Some('=') => {
                 self.read_char();
                 Some(Token::Equal)
@@ -44,7 +74,8 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     Some(Token::NotEqual)
                 } else {
-                    None
+                    self.read_char();
+                    Some(Token::Unknown('!'))
                 }
             }
             Some('<') => {
@@ -52,6 +83,10 @@ impl<'a> Lexer<'a> {
                     self.read_char();
                     self.read_char();
                     Some(Token::LessThanOrEqual)
+                } else if self.peek_char == Some('>') {
+                    self.read_char();
+                    self.read_char();
+                    Some(Token::NotEqual)
                 } else {
                     self.read_char();
                     Some(Token::LessThan)
@@ -71,10 +106,30 @@ impl<'a> Lexer<'a> {
                 self.read_char();
                 Some(Token::Asterisk)
             }
+            Some('+') => {
+                self.read_char();
+                Some(Token::Plus)
+            }
+            Some('-') => {
+                self.read_char();
+                Some(Token::Minus)
+            }
+            Some('/') => {
+                self.read_char();
+                Some(Token::Slash)
+            }
+            Some('%') => {
+                self.read_char();
+                Some(Token::Percent)
+            }
             Some(',') => {
                 self.read_char();
                 Some(Token::Comma)
             }
+            Some(';') => {
+                self.read_char();
+                Some(Token::Semicolon)
+            }
             Some('(') => {
                 self.read_char();
                 Some(Token::LeftParen)
@@ -83,13 +138,19 @@ impl<'a> Lexer<'a> {
                 self.read_char();
                 Some(Token::RightParen)
             }
+            Some('.') if self.peek_char.is_some_and(|c| c.is_ascii_digit()) => self.read_number(),
             Some('.') => {
                 self.read_char();
                 Some(Token::Dot)
             }
-            Some(_c) => {
+            Some('@') => {
                 self.read_char();
-                None
+                self.read_variable()
+            }
+            Some('?' | ':' | '$') => self.read_parameter(),
+            Some(c) => {
+                self.read_char();
+                Some(Token::Unknown(c))
             }
             None => None,
         };
@@ -97,76 +158,472 @@ impl<'a> Lexer<'a> {
         token
     }
 
-    fn skip_whitespace(&mut self) {
+    /// Skips whitespace, `-- line` comments and `/* block */` comments. An
+    /// unterminated block comment runs to the end of the input.
+    fn skip_whitespace_and_comments(&mut self) {
         while let Some(c) = self.current_char {
             if c.is_whitespace() {
                 self.read_char();
+            } else if (c == '-' && self.peek_char == Some('-'))
+                || (c == '#' && self.dialect.hash_comments())
+            {
+                self.read_while(|c| c != '\n');
+            } else if c == '/' && self.peek_char == Some('*') {
+                self.read_char();
+                self.read_char();
+                while self.current_char.is_some()
+                    && !(self.current_char == Some('*') && self.peek_char == Some('/'))
+                {
+                    self.read_char();
+                }
+                self.read_char();
+                self.read_char();
             } else {
                 break;
             }
         }
     }
 
-    fn read_identifier(&mut self) -> Option<Token> {
-        let mut identifier = String::new();
+    /// Advances past characters matching `predicate` and returns them.
+    fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
         while let Some(c) = self.current_char {
-            if c.is_alphanumeric() || c == '_' {
-                identifier.push(c);
+            if predicate(c) {
                 self.read_char();
             } else {
                 break;
             }
         }
+        &self.input[start..self.position]
+    }
+
+    fn read_identifier(&mut self) -> Option<Token<'a>> {
+        let identifier = self.read_while(|c| c.is_alphanumeric() || c == '_');
 
-        if is_keyword(&identifier) {
-            Some(Token::Keyword(identifier.to_uppercase()))
-        } else if is_boolean(&identifier) {
+        let keyword = Keyword::lookup(identifier).filter(|k| self.dialect.recognizes(*k));
+        if let Some(keyword) = keyword {
+            Some(Token::Keyword(keyword))
+        } else if is_boolean(identifier) {
             Some(Token::Boolean(identifier.eq_ignore_ascii_case("TRUE")))
+        } else if identifier.eq_ignore_ascii_case("NULL") {
+            Some(Token::Null)
         } else {
-            Some(Token::Identifier(identifier))
+            Some(Token::Identifier(Cow::Borrowed(identifier)))
         }
     }
 
-    fn read_number(&mut self) -> Option<Token> {
-        let mut number = String::new();
-        while let Some(c) = self.current_char {
-            if c.is_digit(10) {
-                number.push(c);
-                self.read_char();
-            } else {
-                break;
-            }
+    fn read_variable(&mut self) -> Option<Token<'a>> {
+        let name = self.read_while(|c| c.is_alphanumeric() || c == '_');
+
+        if name.is_empty() {
+            Some(Token::Unknown('@'))
+        } else {
+            Some(Token::Variable(Cow::Borrowed(name)))
+        }
+    }
+
+    /// Reads `?` or `?NNN`, or `:name` or `$name`. The prefix is kept, so
+    /// `:a` and `$a` are different parameters, as in SQLite.
+    fn read_parameter(&mut self) -> Option<Token<'a>> {
+        let start = self.position;
+        let prefix = self.current_char?;
+        self.read_char();
+        let suffix = if prefix == '?' {
+            self.read_while(|c| c.is_ascii_digit())
+        } else {
+            self.read_while(|c| c.is_alphanumeric() || c == '_')
+        };
+
+        if prefix != '?' && suffix.is_empty() {
+            Some(Token::Unknown(prefix))
+        } else {
+            Some(Token::Parameter(Cow::Borrowed(
+                &self.input[start..self.position],
+            )))
         }
+    }
 
+    /// Reads a number: decimal digits with an optional fraction and
+    /// exponent, such as `1.5e-3` or `.5`, or a hexadecimal integer such
+    /// as `0x1F`. As in SQLite, hexadecimal integers are 64-bit two's
+    /// complement, so `0xFFFFFFFFFFFFFFFF` is -1.
+    fn read_number(&mut self) -> Option<Token<'a>> {
+        let start = self.position;
+        if self.current_char == Some('0')
+            && matches!(self.peek_char, Some('x' | 'X'))
+            && self.input[start + 2..].starts_with(|c: char| c.is_ascii_hexdigit())
+        {
+            self.read_char();
+            self.read_char();
+            let digits = self.read_while(|c| c.is_ascii_hexdigit());
+            return Some(
+                u64::from_str_radix(digits, 16)
+                    .map_or(Token::Error("Integer literal out of range."), |i| {
+                        Token::Integer(i as i64)
+                    }),
+            );
+        }
+
+        self.read_while(|c| c.is_ascii_digit());
+        let mut is_float = false;
         if self.current_char == Some('.') {
-            number.push('.');
+            is_float = true;
             self.read_char();
-            while let Some(c) = self.current_char {
-                if c.is_digit(10) {
-                    number.push(c);
+            self.read_while(|c| c.is_ascii_digit());
+        }
+        if matches!(self.current_char, Some('e' | 'E')) {
+            let mut exponent = self.input[self.position + 1..].chars();
+            let first = exponent.next();
+            let digit = match first {
+                Some('+' | '-') => exponent.next(),
+                _ => first,
+            };
+            if digit.is_some_and(|c| c.is_ascii_digit()) {
+                is_float = true;
+                self.read_char();
+                if matches!(self.current_char, Some('+' | '-')) {
                     self.read_char();
-                } else {
-                    break;
                 }
+                self.read_while(|c| c.is_ascii_digit());
             }
+        }
+
+        let number = &self.input[start..self.position];
+        if is_float {
             number.parse::<f64>().ok().map(Token::Float)
         } else {
-            number.parse::<i64>().ok().map(Token::Integer)
+            Some(number.parse::<i64>().map_or(
+                Token::Error("Integer literal out of range."),
+                Token::Integer,
+            ))
+        }
+    }
+
+    /// Reads an identifier in the dialect's quotes. It is never a keyword, and
+    /// a doubled closing quote stands for the quote itself.
+    fn read_quoted_identifier(&mut self) -> Option<Token<'a>> {
+        let close = self
+            .current_char
+            .and_then(|c| self.dialect.identifier_quote(c))?;
+        self.read_char(); // Skip opening quote
+        let mut identifier = Cow::Borrowed(self.read_while(|c| c != close));
+        while self.current_char == Some(close) && self.peek_char == Some(close) {
+            self.read_char();
+            self.read_char();
+            let identifier = identifier.to_mut();
+            identifier.push(close);
+            identifier.push_str(self.read_while(|c| c != close));
         }
+        if self.current_char != Some(close) {
+            return Some(Token::Error("Unterminated quoted identifier."));
+        }
+        self.read_char(); // Skip closing quote
+        Some(Token::Identifier(identifier))
     }
 
-    fn read_string_literal(&mut self) -> Option<Token> {
+    /// Reads `X'...'`: an even number of hexadecimal digits, two per byte.
+    fn read_blob(&mut self) -> Option<Token<'a>> {
+        self.read_char(); // Skip X
         self.read_char(); // Skip opening '
-        let mut string = String::new();
+        let digits = self.read_while(|c| c != '\'');
+        if self.current_char.is_none() {
+            return Some(Token::Error("Unterminated blob literal."));
+        }
+        self.read_char(); // Skip closing '
+
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Some(Token::Error("Invalid hex digit in blob."));
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Some(Token::Error("Odd number of hex digits in blob."));
+        }
+        let bytes = (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .ok()?;
+        Some(Token::Blob(bytes))
+    }
+
+    fn read_string_literal(&mut self) -> Option<Token<'a>> {
+        self.read_char(); // Skip opening '
+        let escapes = self.dialect.backslash_escapes();
+        let string = self.read_while(|c| c != '\'' && !(escapes && c == '\\'));
+        if self.current_char.is_none() {
+            return Some(Token::Error("Unterminated string literal."));
+        }
+        let doubled_quote = self.current_char == Some('\'') && self.peek_char == Some('\'');
+        if self.current_char != Some('\\') && !doubled_quote {
+            self.read_char(); // Skip closing '
+            return Some(Token::StringLiteral(Cow::Borrowed(string)));
+        }
+
+        // Only strings that contain escapes are copied
+        let mut unescaped = string.to_string();
         while let Some(c) = self.current_char {
             if c == '\'' {
-                self.read_char(); // Skip closing '
-                break;
-            } else {
-                string.push(c);
+                // `''` stands for a single quote in every dialect
+                if self.peek_char != Some('\'') {
+                    break;
+                }
                 self.read_char();
+                unescaped.push('\'');
+            } else if c == '\\' && escapes {
+                self.read_char();
+                match self.current_char {
+                    Some('n') => unescaped.push('\n'),
+                    Some('t') => unescaped.push('\t'),
+                    Some('r') => unescaped.push('\r'),
+                    Some('0') => unescaped.push('\0'),
+                    Some(c) => unescaped.push(c),
+                    None => break,
+                }
+            } else {
+                unescaped.push(c);
             }
+            self.read_char();
+        }
+        if self.current_char.is_none() {
+            return Some(Token::Error("Unterminated string literal."));
         }
-        Some(Token::StringLiteral(string))
+        self.read_char(); // Skip closing '
+        Some(Token::StringLiteral(Cow::Owned(unescaped)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that tokens borrow their text from the input.
+    #[test]
+    fn test_tokens_borrow_input() {
+        let mut lexer = Lexer::new("select name FROM users WHERE bio = 'héllo' AND id >= 10");
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+
+        assert_eq!(tokens.len(), 12);
+        assert!(matches!(tokens[0], Token::Keyword(Keyword::Select)));
+        assert!(matches!(
+            tokens[1],
+            Token::Identifier(Cow::Borrowed("name"))
+        ));
+        assert!(matches!(
+            tokens[7],
+            Token::StringLiteral(Cow::Borrowed("héllo"))
+        ));
+        assert_eq!(tokens[10], Token::GreaterThanOrEqual);
+        assert_eq!(tokens[11], Token::Integer(10));
+
+        // Owned tokens outlive the input they were lexed from
+        let owned: Token<'static> = {
+            let input = String::from("users");
+            Lexer::new(&input).next_token().unwrap().into_owned()
+        };
+        assert_eq!(owned, Token::Identifier(Cow::Owned("users".to_string())));
+    }
+
+    /// Tests the lexical rules that differ between dialects.
+    #[test]
+    fn test_dialects() {
+        let tokens = |sql, dialect| {
+            let mut lexer = Lexer::with_dialect(sql, dialect);
+            let mut tokens = Vec::new();
+            while let Some(token) = lexer.next_token() {
+                tokens.push(token.into_owned());
+            }
+            tokens
+        };
+        let identifier = |name: &str| Token::Identifier(Cow::Owned(name.to_string()));
+
+        assert_eq!(
+            tokens(r#""select" [a b] `c``d`"#, Dialect::Sqlite),
+            vec![identifier("select"), identifier("a b"), identifier("c`d")]
+        );
+        assert_eq!(
+            tokens("`c`", Dialect::Ansi),
+            vec![Token::Unknown('`'), identifier("c"), Token::Unknown('`')]
+        );
+        assert_eq!(
+            tokens("temp # comment\n 'it\\'s\\n'", Dialect::MySql),
+            vec![
+                identifier("temp"),
+                Token::StringLiteral(Cow::Owned("it's\n".to_string()))
+            ]
+        );
+        assert_eq!(
+            tokens("temp 'a\\'", Dialect::Sqlite),
+            vec![
+                Token::Keyword(Keyword::Temp),
+                Token::StringLiteral(Cow::Owned("a\\".to_string()))
+            ]
+        );
+    }
+
+    /// Tests that line and block comments are skipped.
+    #[test]
+    fn test_comments() {
+        let mut lexer = Lexer::new("SELECT -- pick\n a /* the\n column */ - b /* unterminated");
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Keyword(Keyword::Select),
+                Token::Identifier(Cow::Borrowed("a")),
+                Token::Minus,
+                Token::Identifier(Cow::Borrowed("b")),
+            ]
+        );
+    }
+
+    /// Tests every operator token.
+    #[test]
+    fn test_operators() {
+        let mut lexer = Lexer::new("= < > <= >= != <> + - * / % . a<>b");
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Equal,
+                Token::LessThan,
+                Token::GreaterThan,
+                Token::LessThanOrEqual,
+                Token::GreaterThanOrEqual,
+                Token::NotEqual,
+                Token::NotEqual,
+                Token::Plus,
+                Token::Minus,
+                Token::Asterisk,
+                Token::Slash,
+                Token::Percent,
+                Token::Dot,
+                Token::Identifier(Cow::Borrowed("a")),
+                Token::NotEqual,
+                Token::Identifier(Cow::Borrowed("b")),
+            ]
+        );
+    }
+
+    /// Tests hexadecimal and exponent numeric literals.
+    #[test]
+    fn test_numeric_literals() {
+        let mut lexer = Lexer::new("0x1F 0XfF 0xFFFFFFFFFFFFFFFF 1.5e-3 2E+2 3e4 .5 7.e 0x");
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Integer(31),
+                Token::Integer(255),
+                Token::Integer(-1),
+                Token::Float(1.5e-3),
+                Token::Float(200.0),
+                Token::Float(3e4),
+                Token::Float(0.5),
+                Token::Float(7.0),
+                Token::Identifier(Cow::Borrowed("e")),
+                Token::Integer(0),
+                Token::Identifier(Cow::Borrowed("x")),
+            ]
+        );
+    }
+
+    /// Tests that a doubled quote inside a string literal is one quote.
+    #[test]
+    fn test_doubled_quotes() {
+        let mut lexer = Lexer::new("'it''s fine' '''' 'plain' ''");
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                Token::StringLiteral(Cow::Owned("it's fine".to_string())),
+                Token::StringLiteral(Cow::Owned("'".to_string())),
+                Token::StringLiteral(Cow::Borrowed("plain")),
+                Token::StringLiteral(Cow::Borrowed("")),
+            ]
+        );
+        assert!(matches!(tokens[2], Token::StringLiteral(Cow::Borrowed(_))));
+
+        let mut lexer = Lexer::with_dialect(r"'a''b\'c'", Dialect::MySql);
+        assert_eq!(
+            lexer.next_token(),
+            Some(Token::StringLiteral(Cow::Owned("a'b'c".to_string())))
+        );
+    }
+
+    /// Tests that unknown characters become tokens instead of ending input.
+    #[test]
+    fn test_unknown_characters() {
+        let mut lexer = Lexer::new("a ! b ~ @ c");
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier(Cow::Borrowed("a")),
+                Token::Unknown('!'),
+                Token::Identifier(Cow::Borrowed("b")),
+                Token::Unknown('~'),
+                Token::Unknown('@'),
+                Token::Identifier(Cow::Borrowed("c")),
+            ]
+        );
+    }
+
+    /// Tests that malformed tokens become error tokens instead of ending input.
+    #[test]
+    fn test_malformed_tokens() {
+        let cases = [
+            ("\"abc", "Unterminated quoted identifier."),
+            ("'abc", "Unterminated string literal."),
+            ("'it''s", "Unterminated string literal."),
+            ("X'ABC", "Unterminated blob literal."),
+            ("X'ABC'", "Odd number of hex digits in blob."),
+            ("X'GG'", "Invalid hex digit in blob."),
+            ("99999999999999999999", "Integer literal out of range."),
+            ("0x10000000000000000", "Integer literal out of range."),
+        ];
+        for (sql, message) in cases {
+            let mut lexer = Lexer::new(sql);
+            assert_eq!(lexer.next_token(), Some(Token::Error(message)), "{}", sql);
+            assert_eq!(lexer.token_span().0, 0);
+        }
+
+        let mut lexer = Lexer::new("X'ABC' a");
+        lexer.next_token();
+        assert_eq!(
+            lexer.next_token(),
+            Some(Token::Identifier(Cow::Borrowed("a")))
+        );
+    }
+
+    /// Tests that keyword lookup ignores case and rejects near misses.
+    #[test]
+    fn test_keyword_lookup() {
+        assert_eq!(
+            Keyword::lookup("autoincrement"),
+            Some(Keyword::Autoincrement)
+        );
+        assert_eq!(Keyword::lookup("Current_Time"), Some(Keyword::CurrentTime));
+        assert_eq!(Keyword::lookup("CURRENT_TIMES"), None);
+        assert_eq!(Keyword::lookup("selec"), None);
+        assert_eq!(Keyword::lookup(""), None);
+        assert!(Keyword::Select.is_reserved());
+        assert!(!Keyword::Index.is_reserved());
     }
 }
@@ -1,13 +1,25 @@
 pub mod ast;
 pub mod buffer_pool;
+pub mod dialect;
 pub mod index;
 pub mod lexer;
 pub mod parser;
+pub mod reader;
 pub mod storage;
 pub mod tokens;
 
-pub use ast::{Expression, Insert, Join, Ordering, Query, Select, SortOrder, Table, Value};
+pub use ast::{
+    AlterTable, AlterTableOperation, AsOf, Assignment, ColumnConstraint, ColumnDef,
+    CommonTableExpression, ConflictAction, CreateIndex, CreateTable, CreateTrigger, CreateView,
+    CurrentTime, Delete, DerivedTable, DropIndex, DropTable, DropView, Expression, IndexHint,
+    IndexedColumn, Insert, Join, JoinKind, Merge, MergeAction, MergeClause, NullsOrder, OnConflict,
+    Ordering, Parameter, Pragma, Privilege, Privileges, Query, SampleUnit, Select, SelectItem,
+    SetOperator, SortOrder, Table, TableFunction, TableReference, TableSample, TransactionMode,
+    TriggerEvent, TriggerTiming, Update, Value,
+};
 pub use buffer_pool::BufferPool;
+pub use dialect::Dialect;
 pub use index::{BPlusTree, ORDER};
-pub use parser::Parser;
+pub use parser::{parse_with_limits, Limits, ParseError, Parser};
+pub use reader::StatementReader;
 pub use storage::StorageEngine;
@@ -1,90 +1,293 @@
 use crate::ast::{
-    BinaryOperator, Expression, Insert, Join, Ordering, Query, Select, SortOrder, Table, Value,
+    AlterTable, AlterTableOperation, AsOf, Assignment, BinaryOperator, ColumnConstraint, ColumnDef,
+    CommonTableExpression, ConflictAction, CreateIndex, CreateTable, CreateTrigger, CreateView,
+    CurrentTime, Delete, DerivedTable, DropIndex, DropTable, DropView, Expression, IndexHint,
+    IndexedColumn, Insert, Join, JoinKind, Merge, MergeAction, MergeClause, NullsOrder, OnConflict,
+    Ordering, Parameter, Pragma, Privilege, Privileges, Query, SampleUnit, Select, SelectItem,
+    SetOperator, SortOrder, Table, TableFunction, TableReference, TableSample, TransactionMode,
+    TriggerEvent, TriggerTiming, UnaryOperator, Update, Value,
 };
+use crate::dialect::Dialect;
 use crate::lexer::Lexer;
-use crate::tokens::Token;
+use crate::tokens::{Keyword, Token};
+
+/// Bounds on the work a parser will do, so that untrusted SQL cannot
+/// overflow the stack or hold unbounded memory.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// How deeply expressions and subqueries may nest.
+    pub max_depth: usize,
+    /// How many tokens a statement may contain.
+    pub max_tokens: usize,
+    /// How many bytes a statement may contain.
+    pub max_statement_len: usize,
+}
+
+impl Default for Limits {
+    /// Only nesting is limited by default, which keeps the parser within
+    /// the stack of a typical thread.
+    fn default() -> Self {
+        Limits {
+            max_depth: 100,
+            max_tokens: usize::MAX,
+            max_statement_len: usize::MAX,
+        }
+    }
+}
+
+/// An error found while parsing, with where in the input it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    /// The 1-based line of the offending token.
+    pub line: usize,
+    /// The 1-based column of the offending token, counted in characters.
+    pub column: usize,
+    /// The byte offset of the offending token.
+    pub offset: usize,
+    /// The offending token as written, or None at the end of the input.
+    pub found: Option<String>,
+    /// What the parser was looking for, when it wanted one thing.
+    pub expected: Option<String>,
+}
+
+impl ParseError {
+    /// Creates an error at byte `offset` of `input`.
+    fn at(input: &str, offset: usize, message: impl Into<String>) -> Self {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            message: message.into(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            offset,
+            found: None,
+            expected: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (line {}, column {})",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a single statement from untrusted input, failing with an error
+/// rather than exhausting the stack or memory when `limits` are exceeded.
+pub fn parse_with_limits(sql: &str, limits: Limits) -> Result<Query, ParseError> {
+    if sql.len() > limits.max_statement_len {
+        return Err(ParseError::at(
+            sql,
+            limits.max_statement_len,
+            format!(
+                "The statement is longer than the limit of {} bytes.",
+                limits.max_statement_len
+            ),
+        ));
+    }
+    let mut parser = Parser::new(sql)?;
+    parser.limits = limits;
+    parser.parse()
+}
 
 pub struct Parser<'a> {
+    input: &'a str,
     lexer: Lexer<'a>,
-    current_token: Option<Token>,
+    current_token: Option<Token<'a>>,
+    /// Byte range of `current_token` in the input.
+    current_span: (usize, usize),
+    limits: Limits,
+    /// Current nesting of expressions and subqueries.
+    depth: usize,
+    /// Tokens read so far, including the current one.
+    token_count: usize,
+    /// Set when a limit is exceeded somewhere a Result cannot be returned.
+    limit_error: Option<ParseError>,
 }
 
 impl<'a> Parser<'a> {
     /// Create a new parser.
-    pub fn new(input: &'a str) -> Result<Self, String> {
-        let mut lexer = Lexer::new(input);
+    pub fn new(input: &'a str) -> Result<Self, ParseError> {
+        Parser::with_dialect(input, Dialect::default())
+    }
+
+    /// Create a parser that reads `input` as SQL written for `dialect`.
+    pub fn with_dialect(input: &'a str, dialect: Dialect) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::with_dialect(input, dialect);
         let first_token = lexer.next_token();
         Ok(Parser {
+            input,
+            current_span: lexer.token_span(),
             lexer,
+            token_count: usize::from(first_token.is_some()),
             current_token: first_token,
+            limits: Limits::default(),
+            depth: 0,
+            limit_error: None,
         })
     }
 
     fn next_token(&mut self) {
         self.current_token = self.lexer.next_token();
+        self.current_span = self.lexer.token_span();
+        if self.current_token.is_none() {
+            return;
+        }
+        if self.token_count >= self.limits.max_tokens {
+            // Ending the input makes the statement fail to parse; `parse`
+            // then reports the limit instead of the resulting syntax error.
+            if self.limit_error.is_none() {
+                self.limit_error = Some(self.error(format!(
+                    "The statement has more than the limit of {} tokens.",
+                    self.limits.max_tokens
+                )));
+            }
+            self.current_token = None;
+            return;
+        }
+        self.token_count += 1;
     }
 
-    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
-        if let Some(Token::Keyword(ref kw)) = self.current_token {
-            if kw.eq_ignore_ascii_case(keyword) {
-                self.next_token();
-                Ok(())
-            } else {
-                Err(format!(
-                    "Expected keyword '{}', but found '{}'",
-                    keyword, kw
-                ))
-            }
+    /// Creates an error at the current token. A malformed token is reported
+    /// as such, whatever was expected in its place.
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let (start, end) = self.current_span;
+        let message = match self.current_token {
+            Some(Token::Error(problem)) => problem.to_string(),
+            _ => message.into(),
+        };
+        let mut error = ParseError::at(self.input, start, message);
+        if self.current_token.is_some() {
+            error.found = Some(self.input[start..end].to_string());
+        }
+        error
+    }
+
+    /// Creates an error for a missing `expected`, at the current token.
+    fn expected(&self, expected: impl std::fmt::Display) -> ParseError {
+        let message = match self.current_token {
+            Some(ref current) => format!("Expected '{}', but found '{}'", expected, current),
+            None => format!("Expected '{}', but reached the end of the input", expected),
+        };
+        let mut error = self.error(message);
+        error.expected = Some(expected.to_string());
+        error
+    }
+
+    /// Runs `parse` one nesting level deeper, failing once the depth limit
+    /// is reached.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(self.error(format!(
+                "The statement is nested deeper than the limit of {}.",
+                self.limits.max_depth
+            )));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expect_keyword(&mut self, keyword: Keyword) -> Result<(), ParseError> {
+        if self.consume_keyword(keyword) {
+            Ok(())
         } else {
-            Err(format!("Expected keyword '{}'", keyword))
+            Err(self.expected(keyword))
         }
     }
 
-    fn consume_keyword(&mut self, keyword: &str) -> bool {
-        if let Some(Token::Keyword(ref kw)) = self.current_token {
-            if kw.eq_ignore_ascii_case(keyword) {
-                self.next_token();
-                true
-            } else {
-                false
-            }
+    fn consume_keyword(&mut self, keyword: Keyword) -> bool {
+        if self.peek_keyword(keyword) {
+            self.next_token();
+            true
         } else {
             false
         }
     }
 
-    fn consume_keywords(&mut self, keywords: &[&str]) -> bool {
-        let original_token = self.current_token.clone();
+    /// Consumes `keywords` if they all come next. Otherwise nothing is
+    /// consumed: the lexer and the current token are put back as they were.
+    fn consume_keywords(&mut self, keywords: &[Keyword]) -> bool {
+        let lexer = self.lexer.clone();
+        let token = self.current_token.clone();
+        let span = self.current_span;
+        let token_count = self.token_count;
+        let limit_error = self.limit_error.clone();
         for &keyword in keywords {
             if !self.consume_keyword(keyword) {
-                self.current_token = original_token.clone();
+                self.lexer = lexer;
+                self.current_token = token;
+                self.current_span = span;
+                self.token_count = token_count;
+                self.limit_error = limit_error;
                 return false;
             }
         }
         true
     }
 
-    fn peek_keyword(&self, keyword: &str) -> bool {
-        if let Some(Token::Keyword(ref kw)) = self.current_token {
-            kw.eq_ignore_ascii_case(keyword)
-        } else {
-            false
+    /// Consumes `word` if it is the current token, as an identifier. Used for
+    /// words that are only special in one place, like `TRANSACTION`.
+    fn consume_word(&mut self, word: &str) -> bool {
+        match self.current_token {
+            Some(Token::Identifier(ref name)) if name.eq_ignore_ascii_case(word) => {
+                self.next_token();
+                true
+            }
+            _ => false,
         }
     }
 
-    fn expect_token(&mut self, expected: &Token) -> Result<(), String> {
-        if let Some(ref current) = self.current_token {
-            if current == expected {
-                self.next_token();
-                Ok(())
-            } else {
-                Err(format!(
-                    "Expected token '{:?}', but found '{:?}'",
-                    expected, current
-                ))
+    /// The current token as a name: an identifier, or a non-reserved
+    /// keyword as it is written, so that words like `INDEX` or `LEFT` can
+    /// still name columns, tables and functions.
+    fn peek_name(&self) -> Option<String> {
+        match self.current_token {
+            Some(Token::Identifier(ref name)) => Some(name.to_string()),
+            Some(Token::Keyword(keyword)) if !keyword.is_reserved() => {
+                let (start, end) = self.current_span;
+                Some(self.input[start..end].to_string())
             }
+            _ => None,
+        }
+    }
+
+    /// Consumes the current token if it is a name, see `peek_name`.
+    fn consume_name(&mut self) -> Option<String> {
+        let name = self.peek_name()?;
+        self.next_token();
+        Some(name)
+    }
+
+    fn peek_keyword(&self, keyword: Keyword) -> bool {
+        self.current_token == Some(Token::Keyword(keyword))
+    }
+
+    /// Whether the token after the current one is `keyword`.
+    fn peek_next_keyword(&self, keyword: Keyword) -> bool {
+        self.lexer.clone().next_token() == Some(Token::Keyword(keyword))
+    }
+
+    fn expect_token(&mut self, expected: &Token) -> Result<(), ParseError> {
+        if self.consume_token(expected) {
+            Ok(())
         } else {
-            Err(format!("Expected token '{:?}', but reached EOF", expected))
+            Err(self.expected(expected))
         }
     }
 
@@ -101,347 +304,3439 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// The entire query is parsed.
-    pub fn parse(&mut self) -> Result<Query, String> {
-        if self.peek_keyword("SELECT") {
+    /// The entire query is parsed. Only a `;` may follow the statement.
+    pub fn parse(&mut self) -> Result<Query, ParseError> {
+        let result = self.parse_statement().and_then(|query| {
+            self.consume_token(&Token::Semicolon);
+            match self.current_token {
+                None => Ok(query),
+                Some(_) => Err(self.expected(Token::Semicolon)),
+            }
+        });
+        self.finish(result)
+    }
+
+    /// Reports an exceeded limit in place of the error it caused, if any.
+    fn finish<T>(&mut self, result: Result<T, ParseError>) -> Result<T, ParseError> {
+        match self.limit_error.take() {
+            Some(error) => Err(error),
+            None => result,
+        }
+    }
+
+    /// Parses a script of statements separated by semicolons, such as a
+    /// migration file. Empty statements are skipped.
+    pub fn parse_statements(&mut self) -> Result<Vec<Query>, ParseError> {
+        let mut queries = Vec::new();
+        loop {
+            while self.consume_token(&Token::Semicolon) {}
+            if self.current_token.is_none() {
+                break;
+            }
+            let query = self.parse_statement();
+            queries.push(self.finish(query)?);
+            if self.current_token.is_some() {
+                self.expect_token(&Token::Semicolon)?;
+            }
+        }
+        self.finish(Ok(queries))
+    }
+
+    fn parse_statement(&mut self) -> Result<Query, ParseError> {
+        if self.peek_keyword(Keyword::With) {
+            self.parse_with()
+        } else if self.peek_keyword(Keyword::Select) {
             self.parse_select()
-        } else if self.peek_keyword("INSERT") {
+        } else if self.peek_keyword(Keyword::Insert) {
             self.parse_insert()
+        } else if self.peek_keyword(Keyword::Truncate) {
+            self.parse_truncate()
+        } else if self.peek_keyword(Keyword::Merge) {
+            self.parse_merge()
+        } else if self.peek_keyword(Keyword::Update) {
+            self.parse_update()
+        } else if self.peek_keyword(Keyword::Delete) {
+            self.parse_delete()
+        } else if self.peek_keyword(Keyword::Create) {
+            self.parse_create()
+        } else if self.peek_keyword(Keyword::Drop) {
+            self.parse_drop()
+        } else if self.peek_keyword(Keyword::Alter) {
+            self.parse_alter()
+        } else if self.peek_keyword(Keyword::Refresh) {
+            self.parse_refresh()
+        } else if self.peek_keyword(Keyword::Set) {
+            self.parse_set_variable()
+        } else if self.peek_keyword(Keyword::Grant) {
+            self.parse_grant()
+        } else if self.peek_keyword(Keyword::Revoke) {
+            self.parse_revoke()
+        } else if self.peek_keyword(Keyword::Explain) {
+            self.parse_explain()
+        } else if self.peek_keyword(Keyword::Pragma) {
+            self.parse_pragma()
+        } else if self.peek_keyword(Keyword::Begin) {
+            self.parse_begin()
+        } else if self.consume_keyword(Keyword::Commit) || self.consume_keyword(Keyword::End) {
+            self.consume_word("TRANSACTION");
+            Ok(Query::Commit)
+        } else if self.peek_keyword(Keyword::Rollback) {
+            self.parse_rollback()
+        } else if self.consume_keyword(Keyword::Savepoint) {
+            Ok(Query::Savepoint(self.parse_object_name()?))
+        } else if self.consume_keyword(Keyword::Release) {
+            self.consume_keyword(Keyword::Savepoint);
+            Ok(Query::Release(self.parse_object_name()?))
         } else {
-            Err("This is an unsupported query type.".to_string())
+            Err(self.error("This is an unsupported query type."))
         }
     }
 
     /// Parses the INSERT statement.
-    fn parse_insert(&mut self) -> Result<Query, String> {
-        self.expect_keyword("INSERT")?;
-        self.expect_keyword("INTO")?;
+    fn parse_insert(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Insert)?;
+        self.expect_keyword(Keyword::Into)?;
         let table = self.parse_table()?;
 
-        self.expect_token(&Token::LeftParen)?;
-        let mut columns = Vec::new();
-        loop {
-            if let Some(Token::Identifier(ref col)) = self.current_token {
-                columns.push(col.clone());
-                self.next_token();
-            } else {
-                return Err("I was expecting a column name.".to_string());
-            }
-
-            if !self.consume_token(&Token::Comma) {
-                break;
-            }
+        if self.consume_keyword(Keyword::Default) {
+            self.expect_keyword(Keyword::Values)?;
+            let returning = self.parse_returning()?;
+            return Ok(Query::Insert(Insert {
+                table,
+                columns: Vec::new(),
+                values: None,
+                select: None,
+                default_values: true,
+                on_conflict: None,
+                returning,
+            }));
         }
-        self.expect_token(&Token::RightParen)?;
 
-        if self.consume_keyword("VALUES") {
-            self.expect_token(&Token::LeftParen)?;
-            let mut values = Vec::new();
+        let columns = if self.consume_token(&Token::LeftParen) {
+            self.parse_identifier_list()?
+        } else {
+            Vec::new()
+        };
+
+        let (values, select) = if self.consume_keyword(Keyword::Values) {
+            let mut rows = Vec::new();
             loop {
-                let value = self.parse_value()?;
-                values.push(value);
+                self.expect_token(&Token::LeftParen)?;
+                rows.push(self.parse_expression_list()?);
+                self.expect_token(&Token::RightParen)?;
 
                 if !self.consume_token(&Token::Comma) {
                     break;
                 }
             }
-            self.expect_token(&Token::RightParen)?;
-
-            Ok(Query::Insert(Insert {
-                table,
-                columns,
-                values: Some(values),
-                select: None,
-            }))
-        } else if self.peek_keyword("SELECT") {
+            (Some(rows), None)
+        } else if self.peek_keyword(Keyword::Select) {
             let select = self.parse_select_inner()?;
-            Ok(Query::Insert(Insert {
-                table,
-                columns,
-                values: None,
-                select: Some(Box::new(select)),
-            }))
+            (None, Some(Box::new(select)))
         } else {
-            Err("'VALUES' or 'SELECT' is required after the column.".to_string())
-        }
+            return Err(self.error("'VALUES' or 'SELECT' is required after the column."));
+        };
+
+        let on_conflict = if self.consume_keywords(&[Keyword::On, Keyword::Conflict]) {
+            Some(self.parse_on_conflict()?)
+        } else {
+            None
+        };
+        let returning = self.parse_returning()?;
+
+        Ok(Query::Insert(Insert {
+            table,
+            columns,
+            values,
+            select,
+            default_values: false,
+            on_conflict,
+            returning,
+        }))
     }
 
-    /// Parse the SELECT statement and wrap it in `Query::Select`.
-    fn parse_select(&mut self) -> Result<Query, String> {
-        let select = self.parse_select_inner()?;
-        Ok(Query::Select(select))
+    /// Parses an optional `RETURNING expr [AS alias], ...` clause.
+    fn parse_returning(&mut self) -> Result<Vec<SelectItem>, ParseError> {
+        let mut items = Vec::new();
+        if self.consume_keyword(Keyword::Returning) {
+            loop {
+                let expression = self.parse_expression()?;
+                let alias = self.parse_alias()?;
+                items.push(SelectItem { expression, alias });
+                if !self.consume_token(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        Ok(items)
     }
 
-    /// A function that parses SELECT statements internally
-    fn parse_select_inner(&mut self) -> Result<Select, String> {
-        self.expect_keyword("SELECT")?;
-        let mut columns = Vec::new();
-        loop {
-            columns.push(self.parse_expression()?);
-            if !self.consume_token(&Token::Comma) {
-                break;
+    /// Parses the rest of an upsert clause, after `ON CONFLICT`.
+    fn parse_on_conflict(&mut self) -> Result<OnConflict, ParseError> {
+        let target = if self.consume_token(&Token::LeftParen) {
+            self.parse_identifier_list()?
+        } else {
+            Vec::new()
+        };
+        self.expect_keyword(Keyword::Do)?;
+        let action = if self.consume_keyword(Keyword::Nothing) {
+            ConflictAction::DoNothing
+        } else {
+            self.expect_keyword(Keyword::Update)?;
+            self.expect_keyword(Keyword::Set)?;
+            let assignments = self.parse_assignments()?;
+            let where_clause = if self.consume_keyword(Keyword::Where) {
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            ConflictAction::DoUpdate {
+                assignments,
+                where_clause,
             }
+        };
+        Ok(OnConflict { target, action })
+    }
+
+    /// Parses the TRUNCATE [TABLE] statement.
+    fn parse_truncate(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Truncate)?;
+        self.consume_keyword(Keyword::Table);
+        let table = self.parse_table()?;
+        Ok(Query::Truncate(table))
+    }
+
+    /// Parses the MERGE statement.
+    fn parse_merge(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Merge)?;
+        self.expect_keyword(Keyword::Into)?;
+        let target = self.parse_table()?;
+        self.expect_keyword(Keyword::Using)?;
+        let source = self.parse_table_reference()?;
+        self.expect_keyword(Keyword::On)?;
+        let condition = self.parse_expression()?;
+
+        let mut clauses = Vec::new();
+        while self.consume_keyword(Keyword::When) {
+            clauses.push(self.parse_merge_clause()?);
+        }
+        if clauses.is_empty() {
+            return Err(self.error("MERGE requires at least one 'WHEN' clause."));
         }
 
-        self.expect_keyword("FROM")?;
-        let (table, joins) = self.parse_table_with_joins()?;
+        Ok(Query::Merge(Merge {
+            target,
+            source,
+            condition,
+            clauses,
+        }))
+    }
 
-        let where_clause = if self.consume_keyword("WHERE") {
-            Some(self.parse_logical_expression()?)
+    /// Parses a MERGE branch after its leading WHEN.
+    fn parse_merge_clause(&mut self) -> Result<MergeClause, ParseError> {
+        let matched = !self.consume_keyword(Keyword::Not);
+        self.expect_keyword(Keyword::Matched)?;
+        let condition = if self.consume_keyword(Keyword::And) {
+            Some(self.parse_expression()?)
         } else {
             None
         };
+        self.expect_keyword(Keyword::Then)?;
 
-        let group_by = if self.consume_keywords(&["GROUP", "BY"]) {
-            Some(self.parse_group_by_clause()?)
+        let action = if self.consume_keyword(Keyword::Update) {
+            self.expect_keyword(Keyword::Set)?;
+            MergeAction::Update(self.parse_assignments()?)
+        } else if self.consume_keyword(Keyword::Delete) {
+            MergeAction::Delete
+        } else if self.consume_keyword(Keyword::Insert) {
+            let columns = if self.consume_token(&Token::LeftParen) {
+                self.parse_identifier_list()?
+            } else {
+                Vec::new()
+            };
+            self.expect_keyword(Keyword::Values)?;
+            self.expect_token(&Token::LeftParen)?;
+            let values = self.parse_expression_list()?;
+            self.expect_token(&Token::RightParen)?;
+            MergeAction::Insert { columns, values }
         } else {
-            None
+            return Err(self.error("'UPDATE', 'DELETE' or 'INSERT' is required after 'THEN'."));
         };
 
-        let having = if self.consume_keyword("HAVING") {
-            Some(self.parse_logical_expression()?)
+        if matched && matches!(action, MergeAction::Insert { .. }) {
+            return Err(self.error("'WHEN MATCHED' cannot be followed by 'INSERT'."));
+        }
+        if !matched && !matches!(action, MergeAction::Insert { .. }) {
+            return Err(self.error("'WHEN NOT MATCHED' can only be followed by 'INSERT'."));
+        }
+
+        Ok(MergeClause {
+            matched,
+            condition,
+            action,
+        })
+    }
+
+    /// Parses the UPDATE statement, including an optional FROM join source.
+    fn parse_update(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Update)?;
+        let table = self.parse_table()?;
+        self.expect_keyword(Keyword::Set)?;
+        let assignments = self.parse_assignments()?;
+
+        let (from, joins) = if self.consume_keyword(Keyword::From) {
+            let (source, joins) = self.parse_table_with_joins()?;
+            (Some(source), joins)
         } else {
-            None
+            (None, Vec::new())
         };
 
-        let order_by = if self.consume_keywords(&["ORDER", "BY"]) {
-            Some(self.parse_order_by_clause()?)
+        let where_clause = if self.consume_keyword(Keyword::Where) {
+            Some(self.parse_expression()?)
         } else {
             None
         };
 
-        Ok(Select {
-            columns,
+        let returning = self.parse_returning()?;
+
+        Ok(Query::Update(Update {
             table,
+            assignments,
+            from,
             joins,
             where_clause,
-            group_by,
-            having,
-            order_by,
-        })
+            returning,
+        }))
     }
 
-    fn parse_table_with_joins(&mut self) -> Result<(Table, Vec<Join>), String> {
+    /// Parses the DELETE statement, including an optional USING join source.
+    fn parse_delete(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Delete)?;
+        self.expect_keyword(Keyword::From)?;
         let table = self.parse_table()?;
-        let mut joins = Vec::new();
-        while self.peek_keyword("JOIN") {
-            let join = self.parse_join_clause()?;
-            joins.push(join);
-        }
-        Ok((table, joins))
-    }
 
-    fn parse_table(&mut self) -> Result<Table, String> {
-        if let Some(Token::Identifier(ref name)) = self.current_token {
-            let table = Table { name: name.clone() };
-            self.next_token();
-            Ok(table)
+        let (using, joins) = if self.consume_keyword(Keyword::Using) {
+            let (source, joins) = self.parse_table_with_joins()?;
+            (Some(source), joins)
         } else {
-            Err("I was expecting a table name".to_string())
-        }
-    }
+            (None, Vec::new())
+        };
 
-    fn parse_join_clause(&mut self) -> Result<Join, String> {
-        self.expect_keyword("JOIN")?;
-        let table = self.parse_table()?;
-        let condition = if self.consume_keyword("ON") {
-            Some(self.parse_logical_expression()?)
+        let where_clause = if self.consume_keyword(Keyword::Where) {
+            Some(self.parse_expression()?)
         } else {
             None
         };
-        Ok(Join { table, condition })
-    }
 
-    fn parse_logical_expression(&mut self) -> Result<Expression, String> {
-        self.parse_or_expression()
+        let returning = self.parse_returning()?;
+
+        Ok(Query::Delete(Delete {
+            table,
+            using,
+            joins,
+            where_clause,
+            returning,
+        }))
     }
 
-    fn parse_or_expression(&mut self) -> Result<Expression, String> {
-        let mut expr = self.parse_and_expression()?;
-        while self.consume_keyword("OR") {
-            let right = self.parse_and_expression()?;
-            expr = Expression::Or(Box::new(expr), Box::new(right));
+    /// Parses the CREATE statements.
+    fn parse_create(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Create)?;
+        let temporary =
+            self.consume_keyword(Keyword::Temp) || self.consume_keyword(Keyword::Temporary);
+        let materialized = self.consume_keyword(Keyword::Materialized);
+        let unique = self.consume_keyword(Keyword::Unique);
+        if !materialized && !unique && self.consume_keyword(Keyword::Table) {
+            self.parse_create_table(temporary)
+        } else if !unique && self.consume_keyword(Keyword::View) {
+            self.parse_create_view(temporary, materialized)
+        } else if !temporary && !materialized && self.consume_keyword(Keyword::Index) {
+            self.parse_create_index(unique)
+        } else if !materialized && !unique && self.consume_keyword(Keyword::Trigger) {
+            self.parse_create_trigger(temporary)
+        } else {
+            Err(self.error("'TABLE', 'VIEW', 'INDEX' or 'TRIGGER' is required after 'CREATE'."))
         }
-        Ok(expr)
     }
 
-    fn parse_and_expression(&mut self) -> Result<Expression, String> {
-        let mut expr = self.parse_not_expression()?;
-        while self.consume_keyword("AND") {
-            let right = self.parse_not_expression()?;
-            expr = Expression::And(Box::new(expr), Box::new(right));
+    /// Parses the rest of CREATE TABLE after the TABLE keyword.
+    fn parse_create_table(&mut self, temporary: bool) -> Result<Query, ParseError> {
+        let if_not_exists = self.parse_if_not_exists()?;
+        let name = self.parse_object_name()?;
+        self.expect_token(&Token::LeftParen)?;
+        let mut columns = Vec::new();
+        loop {
+            columns.push(self.parse_column_def()?);
+            if !self.consume_token(&Token::Comma) {
+                break;
+            }
         }
-        Ok(expr)
+        self.expect_token(&Token::RightParen)?;
+
+        Ok(Query::CreateTable(CreateTable {
+            name,
+            temporary,
+            if_not_exists,
+            columns,
+        }))
     }
 
-    fn parse_not_expression(&mut self) -> Result<Expression, String> {
-        if self.consume_keyword("NOT") {
-            let expr = self.parse_primary_expression()?;
-            Ok(Expression::Not(Box::new(expr)))
-        } else {
-            self.parse_primary_expression()
+    /// Parses a column name and its optional type.
+    fn parse_column_def(&mut self) -> Result<ColumnDef, ParseError> {
+        let name = self.parse_object_name()?;
+        let data_type = self.parse_type_name()?;
+        let mut constraints = Vec::new();
+        while let Some(constraint) = self.parse_column_constraint()? {
+            constraints.push(constraint);
         }
+        Ok(ColumnDef {
+            name,
+            data_type,
+            constraints,
+        })
     }
 
-    fn parse_primary_expression(&mut self) -> Result<Expression, String> {
-        if self.consume_token(&Token::LeftParen) {
-            let expr = self.parse_logical_expression()?;
+    /// Parses a column constraint, if one follows.
+    fn parse_column_constraint(&mut self) -> Result<Option<ColumnConstraint>, ParseError> {
+        let constraint = if self.consume_keyword(Keyword::Primary) {
+            // KEY is matched as a plain word, as `key` is a common name
+            if !self.consume_word("KEY") {
+                return Err(self.expected("KEY"));
+            }
+            let direction = self.parse_sort_order();
+            let autoincrement = self.consume_keyword(Keyword::Autoincrement);
+            ColumnConstraint::PrimaryKey {
+                direction,
+                autoincrement,
+            }
+        } else if self.consume_keyword(Keyword::Not) {
+            self.expect_token(&Token::Null)?;
+            ColumnConstraint::NotNull
+        } else if self.consume_keyword(Keyword::Unique) {
+            ColumnConstraint::Unique
+        } else if self.consume_keyword(Keyword::Default) {
+            // A signed number or a term; a full expression must be
+            // parenthesized, so a following COLLATE is the column's own
+            let operator = if self.consume_token(&Token::Minus) {
+                Some(UnaryOperator::Minus)
+            } else if self.consume_token(&Token::Plus) {
+                Some(UnaryOperator::Plus)
+            } else {
+                None
+            };
+            let value = match operator {
+                Some(UnaryOperator::Minus) if self.consume_i64_min_magnitude() => {
+                    Expression::Integer(i64::MIN)
+                }
+                Some(operator) => Expression::Unary {
+                    operator,
+                    expression: Box::new(self.parse_term()?),
+                },
+                None => self.parse_term()?,
+            };
+            ColumnConstraint::Default(value)
+        } else if self.consume_keyword(Keyword::Check) {
+            self.expect_token(&Token::LeftParen)?;
+            let condition = self.parse_expression()?;
             self.expect_token(&Token::RightParen)?;
-            Ok(expr)
+            ColumnConstraint::Check(condition)
+        } else if self.consume_keyword(Keyword::Collate) {
+            match self.current_token {
+                Some(Token::Identifier(ref name)) => {
+                    let collation = name.to_uppercase();
+                    self.next_token();
+                    ColumnConstraint::Collate(collation)
+                }
+                _ => return Err(self.error("I was expecting a collation name.")),
+            }
+        } else if self.consume_keyword(Keyword::References) {
+            let table = self.parse_object_name()?;
+            let columns = if self.consume_token(&Token::LeftParen) {
+                self.parse_identifier_list()?
+            } else {
+                Vec::new()
+            };
+            ColumnConstraint::References { table, columns }
         } else {
-            self.parse_comparison_expression()
-        }
+            return Ok(None);
+        };
+        Ok(Some(constraint))
     }
 
-    fn parse_comparison_expression(&mut self) -> Result<Expression, String> {
-        let left = self.parse_term()?;
-        if let Some(op) = self.current_token.clone() {
-            let operator = match op {
-                Token::Equal => Some(BinaryOperator::Equal),
-                Token::NotEqual => Some(BinaryOperator::NotEqual),
-                Token::LessThan => Some(BinaryOperator::LessThan),
-                Token::LessThanOrEqual => Some(BinaryOperator::LessThanOrEqual),
-                Token::GreaterThan => Some(BinaryOperator::GreaterThan),
-                Token::GreaterThanOrEqual => Some(BinaryOperator::GreaterThanOrEqual),
-                _ => None,
-            };
+    /// Parses an optional type name: one or more words, such as
+    /// `DOUBLE PRECISION`, optionally followed by `(n)` or `(n, m)`.
+    fn parse_type_name(&mut self) -> Result<Option<String>, ParseError> {
+        let mut words = Vec::new();
+        while let Some(Token::Identifier(ref word)) = self.current_token {
+            words.push(word.to_string());
+            self.next_token();
+        }
+        if words.is_empty() {
+            return Ok(None);
+        }
 
-            if let Some(op) = operator {
+        let mut type_name = words.join(" ");
+        if self.consume_token(&Token::LeftParen) {
+            let mut sizes = Vec::new();
+            loop {
+                match self.current_token {
+                    Some(Token::Integer(size)) => sizes.push(size.to_string()),
+                    _ => return Err(self.error("I was expecting a type size.")),
+                }
                 self.next_token();
-                let right = self.parse_term()?;
-                Ok(Expression::Binary {
-                    left: Box::new(left),
-                    operator: op,
-                    right: Box::new(right),
-                })
-            } else {
-                Ok(left)
+                if !self.consume_token(&Token::Comma) {
+                    break;
+                }
             }
-        } else {
-            Ok(left)
+            self.expect_token(&Token::RightParen)?;
+            type_name = format!("{}({})", type_name, sizes.join(", "));
         }
+        Ok(Some(type_name))
     }
 
-    fn parse_group_by_clause(&mut self) -> Result<Vec<Expression>, String> {
-        let mut expressions = Vec::new();
+    /// Parses the rest of CREATE INDEX after the INDEX keyword.
+    fn parse_create_index(&mut self, unique: bool) -> Result<Query, ParseError> {
+        let if_not_exists = self.parse_if_not_exists()?;
+        let name = self.parse_object_name()?;
+        self.expect_keyword(Keyword::On)?;
+        let table = self.parse_object_name()?;
+        self.expect_token(&Token::LeftParen)?;
+        let mut columns = Vec::new();
         loop {
-            expressions.push(self.parse_expression()?);
+            let name = self.parse_object_name()?;
+            let direction = self.parse_sort_order();
+            columns.push(IndexedColumn { name, direction });
             if !self.consume_token(&Token::Comma) {
                 break;
             }
         }
-        Ok(expressions)
-    }
+        self.expect_token(&Token::RightParen)?;
 
-    fn parse_order_by_clause(&mut self) -> Result<Vec<Ordering>, String> {
-        let mut orderings = Vec::new();
-        loop {
-            let expr = self.parse_expression()?;
-            let direction = if self.consume_keyword("ASC") {
-                SortOrder::Ascending
-            } else if self.consume_keyword("DESC") {
-                SortOrder::Descending
-            } else {
-                SortOrder::Ascending
-            };
-            orderings.push(Ordering {
-                expression: expr,
-                direction,
-            });
-            if !self.consume_token(&Token::Comma) {
-                break;
-            }
-        }
-        Ok(orderings)
+        Ok(Query::CreateIndex(CreateIndex {
+            name,
+            table,
+            unique,
+            if_not_exists,
+            columns,
+        }))
     }
 
-    fn parse_expression(&mut self) -> Result<Expression, String> {
-        self.parse_logical_expression()
-    }
+    /// Parses the rest of CREATE TRIGGER after the TRIGGER keyword.
+    fn parse_create_trigger(&mut self, temporary: bool) -> Result<Query, ParseError> {
+        let if_not_exists = self.parse_if_not_exists()?;
+        let name = self.parse_object_name()?;
 
-    fn parse_value(&mut self) -> Result<Value, String> {
-        match self.current_token.clone() {
-            Some(Token::Integer(i)) => {
-                self.next_token();
-                Ok(Value::Integer(i))
+        let timing = if self.consume_keyword(Keyword::Before) {
+            TriggerTiming::Before
+        } else if self.consume_keyword(Keyword::After) {
+            TriggerTiming::After
+        } else if self.consume_keyword(Keyword::Instead) {
+            self.expect_keyword(Keyword::Of)?;
+            TriggerTiming::InsteadOf
+        } else {
+            TriggerTiming::default()
+        };
+
+        let event = if self.consume_keyword(Keyword::Insert) {
+            TriggerEvent::Insert
+        } else if self.consume_keyword(Keyword::Delete) {
+            TriggerEvent::Delete
+        } else if self.consume_keyword(Keyword::Update) {
+            let mut columns = Vec::new();
+            if self.consume_keyword(Keyword::Of) {
+                loop {
+                    columns.push(self.parse_object_name()?);
+                    if !self.consume_token(&Token::Comma) {
+                        break;
+                    }
+                }
             }
-            Some(Token::Float(f)) => {
-                self.next_token();
-                Ok(Value::Float(f))
+            TriggerEvent::Update(columns)
+        } else {
+            return Err(self.error("'INSERT', 'UPDATE' or 'DELETE' is required in a trigger."));
+        };
+
+        self.expect_keyword(Keyword::On)?;
+        let table = self.parse_object_name()?;
+        let for_each_row = self.consume_keyword(Keyword::For);
+        if for_each_row {
+            self.expect_keyword(Keyword::Each)?;
+            if !self.consume_word("ROW") {
+                return Err(self.expected("ROW"));
             }
-            Some(Token::StringLiteral(ref s)) => {
-                self.next_token();
-                Ok(Value::Text(s.clone()))
+        }
+        let when = if self.consume_keyword(Keyword::When) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        self.expect_keyword(Keyword::Begin)?;
+        let mut body = Vec::new();
+        while !self.consume_keyword(Keyword::End) {
+            let statement = if self.peek_keyword(Keyword::Select) {
+                self.parse_select()?
+            } else if self.peek_keyword(Keyword::Insert) {
+                self.parse_insert()?
+            } else if self.peek_keyword(Keyword::Update) {
+                self.parse_update()?
+            } else if self.peek_keyword(Keyword::Delete) {
+                self.parse_delete()?
+            } else {
+                return Err(
+                    self.error("Only SELECT, INSERT, UPDATE and DELETE are allowed in a trigger.")
+                );
+            };
+            body.push(statement);
+            self.expect_token(&Token::Semicolon)?;
+        }
+        if body.is_empty() {
+            return Err(self.error("A trigger needs at least one statement."));
+        }
+
+        Ok(Query::CreateTrigger(CreateTrigger {
+            name,
+            temporary,
+            if_not_exists,
+            timing,
+            event,
+            table,
+            for_each_row,
+            when,
+            body,
+        }))
+    }
+
+    /// Parses the rest of CREATE VIEW after the VIEW keyword.
+    fn parse_create_view(
+        &mut self,
+        temporary: bool,
+        materialized: bool,
+    ) -> Result<Query, ParseError> {
+        let if_not_exists = self.parse_if_not_exists()?;
+        let name = self.parse_object_name()?;
+        let columns = if self.consume_token(&Token::LeftParen) {
+            self.parse_identifier_list()?
+        } else {
+            Vec::new()
+        };
+        self.expect_keyword(Keyword::As)?;
+        let query = self.parse_select_inner()?;
+        Ok(Query::CreateView(CreateView {
+            name,
+            temporary,
+            materialized,
+            if_not_exists,
+            columns,
+            query: Box::new(query),
+        }))
+    }
+
+    /// Parses the DROP statements.
+    fn parse_drop(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Drop)?;
+        let materialized = self.consume_keyword(Keyword::Materialized);
+        if !materialized && self.consume_keyword(Keyword::Table) {
+            let if_exists = self.parse_if_exists()?;
+            let name = self.parse_object_name()?;
+            Ok(Query::DropTable(DropTable { name, if_exists }))
+        } else if !materialized && self.consume_keyword(Keyword::Index) {
+            let if_exists = self.parse_if_exists()?;
+            let name = self.parse_object_name()?;
+            Ok(Query::DropIndex(DropIndex { name, if_exists }))
+        } else if self.consume_keyword(Keyword::View) {
+            let if_exists = self.parse_if_exists()?;
+            let name = self.parse_object_name()?;
+            Ok(Query::DropView(DropView {
+                name,
+                materialized,
+                if_exists,
+            }))
+        } else {
+            Err(self.error("'TABLE', 'VIEW' or 'INDEX' is required after 'DROP'."))
+        }
+    }
+
+    /// Parses the ALTER TABLE statement.
+    fn parse_alter(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Alter)?;
+        self.expect_keyword(Keyword::Table)?;
+        let name = self.parse_object_name()?;
+
+        let operation = if self.consume_keyword(Keyword::Rename) {
+            if self.consume_keyword(Keyword::To) {
+                AlterTableOperation::RenameTable(self.parse_object_name()?)
+            } else {
+                self.consume_keyword(Keyword::Column);
+                let old_name = self.parse_object_name()?;
+                self.expect_keyword(Keyword::To)?;
+                let new_name = self.parse_object_name()?;
+                AlterTableOperation::RenameColumn { old_name, new_name }
             }
-            Some(Token::Null) => {
-                self.next_token();
-                Ok(Value::Null)
+        } else if self.consume_keyword(Keyword::Add) {
+            self.consume_keyword(Keyword::Column);
+            AlterTableOperation::AddColumn(self.parse_column_def()?)
+        } else if self.consume_keyword(Keyword::Drop) {
+            self.consume_keyword(Keyword::Column);
+            AlterTableOperation::DropColumn(self.parse_object_name()?)
+        } else {
+            return Err(self.error("'RENAME', 'ADD' or 'DROP' is required after 'ALTER TABLE'."));
+        };
+
+        Ok(Query::AlterTable(AlterTable { name, operation }))
+    }
+
+    /// Parses REFRESH MATERIALIZED VIEW name.
+    fn parse_refresh(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Refresh)?;
+        self.expect_keyword(Keyword::Materialized)?;
+        self.expect_keyword(Keyword::View)?;
+        let name = self.parse_object_name()?;
+        Ok(Query::RefreshMaterializedView(name))
+    }
+
+    /// Parses SET @name = value.
+    fn parse_set_variable(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Set)?;
+        let name = if let Some(Token::Variable(ref name)) = self.current_token {
+            name.to_string()
+        } else {
+            return Err(self.error("I was expecting a variable such as '@name'."));
+        };
+        self.next_token();
+        self.expect_token(&Token::Equal)?;
+        let value = self.parse_expression()?;
+        Ok(Query::SetVariable { name, value })
+    }
+
+    /// Parses the GRANT statement.
+    fn parse_grant(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Grant)?;
+        Ok(Query::Grant(self.parse_privileges(Keyword::To)?))
+    }
+
+    /// Parses the REVOKE statement.
+    fn parse_revoke(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Revoke)?;
+        Ok(Query::Revoke(self.parse_privileges(Keyword::From)?))
+    }
+
+    /// Parses the rest of GRANT or REVOKE: the privilege list, the table, and
+    /// the users introduced by `preposition` (TO or FROM).
+    fn parse_privileges(&mut self, preposition: Keyword) -> Result<Privileges, ParseError> {
+        let mut privileges = Vec::new();
+        loop {
+            let privilege = if self.consume_keyword(Keyword::All) {
+                self.consume_keyword(Keyword::Privileges);
+                Privilege::All
+            } else if self.consume_keyword(Keyword::Select) {
+                Privilege::Select
+            } else if self.consume_keyword(Keyword::Insert) {
+                Privilege::Insert
+            } else if self.consume_keyword(Keyword::Update) {
+                Privilege::Update
+            } else if self.consume_keyword(Keyword::Delete) {
+                Privilege::Delete
+            } else if self.consume_keyword(Keyword::Alter) {
+                Privilege::Alter
+            } else {
+                return Err(self.error("I was expecting a privilege."));
+            };
+            privileges.push(privilege);
+
+            if !self.consume_token(&Token::Comma) {
+                break;
             }
-            Some(Token::Boolean(b)) => {
-                self.next_token();
-                Ok(Value::Boolean(b))
+        }
+
+        self.expect_keyword(Keyword::On)?;
+        self.consume_keyword(Keyword::Table);
+        let table = self.parse_object_name()?;
+        self.expect_keyword(preposition)?;
+        let mut users = Vec::new();
+        loop {
+            users.push(self.parse_object_name()?);
+            if !self.consume_token(&Token::Comma) {
+                break;
             }
-            _ => Err("This is an unexpected token.".to_string()),
+        }
+
+        Ok(Privileges {
+            privileges,
+            table,
+            users,
+        })
+    }
+
+    /// Parses an optional `IF NOT EXISTS`.
+    fn parse_if_not_exists(&mut self) -> Result<bool, ParseError> {
+        if self.peek_keyword(Keyword::If) && self.peek_next_keyword(Keyword::Not) {
+            self.next_token();
+            self.next_token();
+            self.expect_keyword(Keyword::Exists)?;
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    fn parse_term(&mut self) -> Result<Expression, String> {
-        match self.current_token.clone() {
-            Some(Token::Identifier(ref name)) => {
-                let identifier = name.clone();
-                self.next_token();
-                if self.consume_token(&Token::Dot) {
-                    if let Some(Token::Identifier(ref field)) = self.current_token {
-                        let field_name = format!("{}.{}", identifier, field);
-                        self.next_token();
-                        Ok(Expression::Identifier(field_name))
-                    } else {
-                        Err("I was expecting a field name.".to_string())
-                    }
-                } else if self.consume_token(&Token::LeftParen) {
-                    let mut args = Vec::new();
-                    if !self.consume_token(&Token::RightParen) {
-                        loop {
-                            let expr = self.parse_expression()?;
-                            args.push(expr);
-                            if self.consume_token(&Token::Comma) {
-                                continue;
-                            } else {
-                                self.expect_token(&Token::RightParen)?;
-                                break;
-                            }
-                        }
-                    }
-                    Ok(Expression::Function(identifier, args))
-                } else {
-                    Ok(Expression::Identifier(identifier))
-                }
-            }
-            Some(Token::Integer(i)) => {
-                self.next_token();
-                Ok(Expression::Integer(i))
+    /// Parses an optional `IF EXISTS`.
+    fn parse_if_exists(&mut self) -> Result<bool, ParseError> {
+        if self.peek_keyword(Keyword::If) && self.peek_next_keyword(Keyword::Exists) {
+            self.next_token();
+            self.next_token();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Parses the name of a schema object such as a view.
+    fn parse_object_name(&mut self) -> Result<String, ParseError> {
+        self.consume_name()
+            .ok_or_else(|| self.error("I was expecting a name."))
+    }
+
+    /// Parses a comma-separated list of `column = expression` pairs.
+    fn parse_assignments(&mut self) -> Result<Vec<Assignment>, ParseError> {
+        let mut assignments = Vec::new();
+        loop {
+            let Some(column) = self.consume_name() else {
+                return Err(self.error("I was expecting a column name."));
+            };
+            self.expect_token(&Token::Equal)?;
+            let value = self.parse_expression()?;
+            assignments.push(Assignment { column, value });
+
+            if !self.consume_token(&Token::Comma) {
+                break;
             }
-            Some(Token::Float(f)) => {
-                self.next_token();
-                Ok(Expression::Float(f))
+        }
+        Ok(assignments)
+    }
+
+    /// Parses a comma-separated list of expressions.
+    fn parse_expression_list(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut expressions = Vec::new();
+        loop {
+            expressions.push(self.parse_expression()?);
+            if !self.consume_token(&Token::Comma) {
+                break;
             }
-            Some(Token::StringLiteral(ref s)) => {
-                self.next_token();
-                Ok(Expression::Text(s.clone()))
+        }
+        Ok(expressions)
+    }
+
+    /// Parses `EXPLAIN [QUERY PLAN]` and the statement it applies to.
+    /// QUERY and PLAN are matched as plain words.
+    fn parse_explain(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Explain)?;
+        let query_plan = self.consume_word("QUERY");
+        if query_plan && !self.consume_word("PLAN") {
+            return Err(self.expected("PLAN"));
+        }
+        if self.peek_keyword(Keyword::Explain) {
+            return Err(self.error("EXPLAIN cannot be applied to EXPLAIN."));
+        }
+        let statement = self.parse_statement()?;
+        Ok(Query::Explain {
+            query_plan,
+            statement: Box::new(statement),
+        })
+    }
+
+    /// Parses `PRAGMA [schema.]name [= value | (value)]`.
+    fn parse_pragma(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Pragma)?;
+        let name = self.parse_object_name()?;
+        let (schema, name) = if self.consume_token(&Token::Dot) {
+            (Some(name), self.parse_object_name()?)
+        } else {
+            (None, name)
+        };
+
+        let value = if self.consume_token(&Token::Equal) {
+            Some(self.parse_pragma_value()?)
+        } else if self.consume_token(&Token::LeftParen) {
+            let value = self.parse_pragma_value()?;
+            self.expect_token(&Token::RightParen)?;
+            Some(value)
+        } else {
+            None
+        };
+        Ok(Query::Pragma(Pragma {
+            schema,
+            name,
+            value,
+        }))
+    }
+
+    /// Parses a pragma's value: a literal, or a bare word such as `WAL`,
+    /// `ON` or `FULL`, which may also be a keyword.
+    fn parse_pragma_value(&mut self) -> Result<Value, ParseError> {
+        let word = match self.current_token {
+            Some(Token::Identifier(ref word)) => word.to_string(),
+            Some(Token::Keyword(keyword)) => keyword.as_str().to_string(),
+            _ => return self.parse_value(),
+        };
+        self.next_token();
+        Ok(Value::Text(word))
+    }
+
+    /// Parses `BEGIN [DEFERRED | IMMEDIATE | EXCLUSIVE] [TRANSACTION]`. The
+    /// mode and TRANSACTION are matched as plain words so they stay usable
+    /// as names.
+    fn parse_begin(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Begin)?;
+        let mode = if self.consume_word("DEFERRED") {
+            TransactionMode::Deferred
+        } else if self.consume_word("IMMEDIATE") {
+            TransactionMode::Immediate
+        } else if self.consume_word("EXCLUSIVE") {
+            TransactionMode::Exclusive
+        } else {
+            TransactionMode::default()
+        };
+        self.consume_word("TRANSACTION");
+        Ok(Query::Begin(mode))
+    }
+
+    /// Parses `ROLLBACK [TRANSACTION] [TO [SAVEPOINT] name]`.
+    fn parse_rollback(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Rollback)?;
+        self.consume_word("TRANSACTION");
+        let savepoint = if self.consume_keyword(Keyword::To) {
+            self.consume_keyword(Keyword::Savepoint);
+            Some(self.parse_object_name()?)
+        } else {
+            None
+        };
+        Ok(Query::Rollback { savepoint })
+    }
+
+    /// Parses a WITH clause and the statement it prefixes.
+    fn parse_with(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::With)?;
+        let recursive = self.consume_keyword(Keyword::Recursive);
+        let mut ctes = Vec::new();
+        loop {
+            let name = self.parse_object_name()?;
+            let columns = if self.consume_token(&Token::LeftParen) {
+                self.parse_identifier_list()?
+            } else {
+                Vec::new()
+            };
+            self.expect_keyword(Keyword::As)?;
+            self.expect_token(&Token::LeftParen)?;
+            let query = self.parse_select_inner()?;
+            self.expect_token(&Token::RightParen)?;
+            ctes.push(CommonTableExpression {
+                name,
+                columns,
+                query: Box::new(query),
+            });
+            if !self.consume_token(&Token::Comma) {
+                break;
             }
-            Some(Token::Null) => {
-                self.next_token();
-                Ok(Expression::Identifier("NULL".to_string()))
+        }
+
+        let body = if self.peek_keyword(Keyword::Select) {
+            self.parse_select()?
+        } else if self.peek_keyword(Keyword::Insert) {
+            self.parse_insert()?
+        } else if self.peek_keyword(Keyword::Update) {
+            self.parse_update()?
+        } else if self.peek_keyword(Keyword::Delete) {
+            self.parse_delete()?
+        } else {
+            return Err(self.error("WITH must be followed by SELECT, INSERT, UPDATE or DELETE."));
+        };
+        Ok(Query::With {
+            recursive,
+            ctes,
+            body: Box::new(body),
+        })
+    }
+
+    /// Parse the SELECT statement and wrap it in `Query::Select`.
+    fn parse_select(&mut self) -> Result<Query, ParseError> {
+        let select = self.parse_select_inner()?;
+        Ok(Query::Select(select))
+    }
+
+    /// A function that parses SELECT statements internally
+    fn parse_select_inner(&mut self) -> Result<Select, ParseError> {
+        self.nested(Self::parse_select_body)
+    }
+
+    /// Parses a select, including any compound operators and the ORDER BY
+    /// and LIMIT that apply to the combined result.
+    fn parse_select_body(&mut self) -> Result<Select, ParseError> {
+        let mut select = self.parse_select_core()?;
+        while let Some(operator) = self.parse_set_operator() {
+            let right = self.parse_select_core()?;
+            select.compound.push((operator, right));
+        }
+
+        select.order_by = if self.consume_keywords(&[Keyword::Order, Keyword::By]) {
+            Some(self.parse_order_by_clause()?)
+        } else {
+            None
+        };
+        (select.limit, select.offset) = self.parse_limit_clause()?;
+        Ok(select)
+    }
+
+    /// Parses a set operator joining two selects, if there is one.
+    fn parse_set_operator(&mut self) -> Option<SetOperator> {
+        if self.consume_keyword(Keyword::Union) {
+            if self.consume_keyword(Keyword::All) {
+                Some(SetOperator::UnionAll)
+            } else {
+                Some(SetOperator::Union)
             }
-            Some(Token::Boolean(b)) => {
-                self.next_token();
-                Ok(Expression::Boolean(b))
+        } else if self.consume_keyword(Keyword::Intersect) {
+            Some(SetOperator::Intersect)
+        } else if self.consume_keyword(Keyword::Except) {
+            Some(SetOperator::Except)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a single SELECT, without ORDER BY or LIMIT.
+    fn parse_select_core(&mut self) -> Result<Select, ParseError> {
+        self.expect_keyword(Keyword::Select)?;
+        let distinct = self.parse_distinct();
+        let mut columns = Vec::new();
+        loop {
+            let expression = self.parse_expression()?;
+            let alias = self.parse_alias()?;
+            columns.push(SelectItem { expression, alias });
+            if !self.consume_token(&Token::Comma) {
+                break;
             }
-            Some(Token::Asterisk) => {
-                self.next_token();
-                Ok(Expression::Asterisk)
+        }
+
+        self.expect_keyword(Keyword::From)?;
+        let (table, joins) = self.parse_table_with_joins()?;
+
+        let where_clause = if self.consume_keyword(Keyword::Where) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        let group_by = if self.consume_keywords(&[Keyword::Group, Keyword::By]) {
+            Some(self.parse_group_by_clause()?)
+        } else {
+            None
+        };
+
+        let having = if self.consume_keyword(Keyword::Having) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        Ok(Select {
+            distinct,
+            columns,
+            table,
+            joins,
+            where_clause,
+            group_by,
+            having,
+            compound: Vec::new(),
+            order_by: None,
+            limit: None,
+            offset: None,
+        })
+    }
+
+    /// Parses an optional DISTINCT or ALL, returning whether it was DISTINCT.
+    fn parse_distinct(&mut self) -> bool {
+        if self.consume_keyword(Keyword::Distinct) {
+            true
+        } else {
+            self.consume_keyword(Keyword::All);
+            false
+        }
+    }
+
+    fn parse_table_with_joins(&mut self) -> Result<(TableReference, Vec<Join>), ParseError> {
+        let table = self.parse_table_reference()?;
+        let mut joins = Vec::new();
+        while let Some((kind, natural)) = self.parse_join_kind()? {
+            let join = self.parse_join_clause(kind, natural)?;
+            joins.push(join);
+        }
+        Ok((table, joins))
+    }
+
+    /// Parses a table name, a table-valued function call, or a parenthesized
+    /// subquery used as a table.
+    fn parse_table_reference(&mut self) -> Result<TableReference, ParseError> {
+        if self.consume_token(&Token::LeftParen) {
+            let subquery = self.parse_select_inner()?;
+            self.expect_token(&Token::RightParen)?;
+            let alias = self.parse_alias()?;
+            let columns = if alias.is_some() && self.consume_token(&Token::LeftParen) {
+                self.parse_identifier_list()?
+            } else {
+                Vec::new()
+            };
+            Ok(TableReference::Derived(DerivedTable {
+                subquery: Box::new(subquery),
+                alias,
+                columns,
+            }))
+        } else if let Some(name) = self.consume_name() {
+            if self.consume_token(&Token::LeftParen) {
+                let arguments = if self.consume_token(&Token::RightParen) {
+                    Vec::new()
+                } else {
+                    let arguments = self.parse_expression_list()?;
+                    self.expect_token(&Token::RightParen)?;
+                    arguments
+                };
+                let alias = self.parse_alias()?;
+                Ok(TableReference::Function(TableFunction {
+                    name,
+                    arguments,
+                    alias,
+                }))
+            } else {
+                Ok(TableReference::Table(self.parse_table_suffix(name)?))
             }
-            _ => Err("This is an unexpected token.".to_string()),
+        } else {
+            Err(self.error("I was expecting a table name"))
         }
     }
+
+    /// Parses `name, name, ...)` after an opening parenthesis has been consumed.
+    fn parse_identifier_list(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut names = Vec::new();
+        loop {
+            if let Some(name) = self.consume_name() {
+                names.push(name);
+            } else {
+                return Err(self.error("I was expecting a column name."));
+            }
+
+            if !self.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect_token(&Token::RightParen)?;
+        Ok(names)
+    }
+
+    fn parse_table(&mut self) -> Result<Table, ParseError> {
+        if let Some(name) = self.consume_name() {
+            self.parse_table_suffix(name)
+        } else {
+            Err(self.error("I was expecting a table name"))
+        }
+    }
+
+    /// Parses what may follow a table name: `.table` if the name was a
+    /// schema, `AS OF ...`, an alias, and an index hint.
+    fn parse_table_suffix(&mut self, name: String) -> Result<Table, ParseError> {
+        let (schema, name) = if self.consume_token(&Token::Dot) {
+            (Some(name), self.parse_object_name()?)
+        } else {
+            (None, name)
+        };
+
+        // `AS OF ...` and `AS alias` share their first keyword
+        let explicit_alias = self.consume_keyword(Keyword::As);
+        let (as_of, alias) = if explicit_alias && self.consume_keyword(Keyword::Of) {
+            let as_of = self.parse_as_of()?;
+            (Some(as_of), self.parse_alias()?)
+        } else {
+            (None, self.parse_alias_name(explicit_alias)?)
+        };
+
+        let sample = if self.consume_keyword(Keyword::Tablesample) {
+            Some(self.parse_table_sample()?)
+        } else {
+            None
+        };
+
+        let index_hint = if self.consume_keyword(Keyword::Indexed) {
+            self.expect_keyword(Keyword::By)?;
+            Some(IndexHint::IndexedBy(self.parse_object_name()?))
+        } else if self.consume_keyword(Keyword::Not) {
+            self.expect_keyword(Keyword::Indexed)?;
+            Some(IndexHint::NotIndexed)
+        } else {
+            None
+        };
+
+        Ok(Table {
+            schema,
+            name,
+            alias,
+            as_of,
+            sample,
+            index_hint,
+        })
+    }
+
+    /// Parses the `(n PERCENT)` or `(n ROWS)` following TABLESAMPLE. The
+    /// units are matched as plain words so they stay usable as column names.
+    fn parse_table_sample(&mut self) -> Result<TableSample, ParseError> {
+        self.expect_token(&Token::LeftParen)?;
+        let size = self.parse_expression()?;
+        let unit = match self.current_token {
+            Some(Token::Identifier(ref word)) if word.eq_ignore_ascii_case("PERCENT") => {
+                SampleUnit::Percent
+            }
+            Some(Token::Identifier(ref word)) if word.eq_ignore_ascii_case("ROWS") => {
+                SampleUnit::Rows
+            }
+            _ => return Err(self.error("'PERCENT' or 'ROWS' is required in TABLESAMPLE.")),
+        };
+        self.next_token();
+        self.expect_token(&Token::RightParen)?;
+        Ok(TableSample { size, unit })
+    }
+
+    /// Parses `TIMESTAMP expr` or `TRANSACTION expr` after `AS OF`.
+    fn parse_as_of(&mut self) -> Result<AsOf, ParseError> {
+        let point = match self.current_token {
+            Some(Token::Identifier(ref word)) if word.eq_ignore_ascii_case("TIMESTAMP") => {
+                AsOf::Timestamp
+            }
+            Some(Token::Identifier(ref word)) if word.eq_ignore_ascii_case("TRANSACTION") => {
+                AsOf::Transaction
+            }
+            _ => return Err(self.error("'TIMESTAMP' or 'TRANSACTION' is required after 'AS OF'.")),
+        };
+        self.next_token();
+        Ok(point(self.parse_collated_term()?))
+    }
+
+    /// Parses an optional `[AS] alias` following a table reference or a
+    /// SELECT-list expression.
+    fn parse_alias(&mut self) -> Result<Option<String>, ParseError> {
+        let explicit = self.consume_keyword(Keyword::As);
+        self.parse_alias_name(explicit)
+    }
+
+    /// Parses the alias name itself, which is required if `AS` was present.
+    /// Only after `AS` may the alias be a non-reserved keyword, since without
+    /// it a keyword such as `LEFT` starts the next clause.
+    fn parse_alias_name(&mut self, explicit: bool) -> Result<Option<String>, ParseError> {
+        if let Some(Token::Identifier(ref alias)) = self.current_token {
+            let alias = alias.to_string();
+            self.next_token();
+            Ok(Some(alias))
+        } else if explicit {
+            if let Some(alias) = self.consume_name() {
+                return Ok(Some(alias));
+            }
+            Err(self.error("I was expecting an alias after 'AS'."))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses the operator that starts a join, such as `LEFT OUTER JOIN`
+    /// or a comma, and whether it is `NATURAL`, or returns None if no join
+    /// follows.
+    fn parse_join_kind(&mut self) -> Result<Option<(JoinKind, bool)>, ParseError> {
+        if self.consume_token(&Token::Comma) {
+            return Ok(Some((JoinKind::Cross, false)));
+        }
+        let natural = self.consume_keyword(Keyword::Natural);
+        let kind = if self.consume_keyword(Keyword::Inner) {
+            JoinKind::Inner
+        } else if self.consume_keyword(Keyword::Cross) {
+            JoinKind::Cross
+        } else if self.consume_keyword(Keyword::Left) {
+            self.consume_keyword(Keyword::Outer);
+            JoinKind::Left
+        } else if self.consume_keyword(Keyword::Right) {
+            self.consume_keyword(Keyword::Outer);
+            JoinKind::Right
+        } else if self.consume_keyword(Keyword::Full) {
+            self.consume_keyword(Keyword::Outer);
+            JoinKind::Full
+        } else if self.peek_keyword(Keyword::Join) || natural {
+            JoinKind::Inner
+        } else {
+            return Ok(None);
+        };
+        self.expect_keyword(Keyword::Join)?;
+        Ok(Some((kind, natural)))
+    }
+
+    /// Parses the table and the `ON` or `USING` constraint of a join.
+    fn parse_join_clause(&mut self, kind: JoinKind, natural: bool) -> Result<Join, ParseError> {
+        let table = self.parse_table_reference()?;
+        let mut using = Vec::new();
+        let condition = if kind == JoinKind::Cross || natural {
+            None
+        } else if self.consume_keyword(Keyword::On) {
+            Some(self.parse_expression()?)
+        } else if self.consume_keyword(Keyword::Using) {
+            self.expect_token(&Token::LeftParen)?;
+            using = self.parse_identifier_list()?;
+            None
+        } else {
+            None
+        };
+        if natural && (self.peek_keyword(Keyword::On) || self.peek_keyword(Keyword::Using)) {
+            return Err(self.error("A NATURAL join cannot also have ON or USING."));
+        }
+        Ok(Join {
+            kind,
+            natural,
+            table,
+            condition,
+            using,
+        })
+    }
+
+    fn parse_group_by_clause(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut expressions = Vec::new();
+        loop {
+            expressions.push(self.parse_expression()?);
+            if !self.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        Ok(expressions)
+    }
+
+    fn parse_order_by_clause(&mut self) -> Result<Vec<Ordering>, ParseError> {
+        let mut orderings = Vec::new();
+        loop {
+            let expr = self.parse_expression()?;
+            let direction = self.parse_sort_order();
+            let nulls = if self.consume_keyword(Keyword::Nulls) {
+                Some(self.parse_nulls_order()?)
+            } else {
+                None
+            };
+            orderings.push(Ordering {
+                expression: expr,
+                direction,
+                nulls,
+            });
+            if !self.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        Ok(orderings)
+    }
+
+    /// Parses an optional `LIMIT count [OFFSET skip]`, or SQLite's
+    /// `LIMIT skip, count`, returning the count and the offset.
+    fn parse_limit_clause(
+        &mut self,
+    ) -> Result<(Option<Expression>, Option<Expression>), ParseError> {
+        if !self.consume_keyword(Keyword::Limit) {
+            return Ok((None, None));
+        }
+        let first = self.parse_expression()?;
+        if self.consume_token(&Token::Comma) {
+            let count = self.parse_expression()?;
+            Ok((Some(count), Some(first)))
+        } else if self.consume_keyword(Keyword::Offset) {
+            let offset = self.parse_expression()?;
+            Ok((Some(first), Some(offset)))
+        } else {
+            Ok((Some(first), None))
+        }
+    }
+
+    /// Parses an optional ASC or DESC, defaulting to ascending.
+    fn parse_sort_order(&mut self) -> SortOrder {
+        if self.consume_keyword(Keyword::Desc) {
+            SortOrder::Descending
+        } else {
+            self.consume_keyword(Keyword::Asc);
+            SortOrder::Ascending
+        }
+    }
+
+    /// Parses the FIRST or LAST following NULLS. They are matched as plain
+    /// words so that `first` and `last` stay usable as column names.
+    fn parse_nulls_order(&mut self) -> Result<NullsOrder, ParseError> {
+        let nulls = match self.current_token {
+            Some(Token::Identifier(ref word)) if word.eq_ignore_ascii_case("FIRST") => {
+                NullsOrder::First
+            }
+            Some(Token::Identifier(ref word)) if word.eq_ignore_ascii_case("LAST") => {
+                NullsOrder::Last
+            }
+            _ => return Err(self.error("'FIRST' or 'LAST' is required after 'NULLS'.")),
+        };
+        self.next_token();
+        Ok(nulls)
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        self.parse_binary_expression(0)
+    }
+
+    /// Parses an expression by precedence climbing: operators that bind
+    /// tighter than `min_precedence` are folded into the left operand, and
+    /// operators of equal precedence associate to the left.
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let mut left = self.parse_prefix_expression()?;
+        while let Some(precedence) = self.infix_precedence() {
+            if precedence < min_precedence {
+                break;
+            }
+            left = self.parse_infix_expression(left, precedence)?;
+        }
+        Ok(left)
+    }
+
+    /// The precedence of the infix operator at the current token, from OR
+    /// (loosest) to `*`, `/` and `%` (tightest), or None if there is none.
+    fn infix_precedence(&self) -> Option<u8> {
+        match self.current_token {
+            Some(Token::Keyword(Keyword::Or)) => Some(1),
+            Some(Token::Keyword(Keyword::And)) => Some(2),
+            Some(Token::Keyword(Keyword::Is | Keyword::In | Keyword::Between | Keyword::Like)) => {
+                Some(4)
+            }
+            Some(Token::Keyword(Keyword::Not))
+                if [Keyword::In, Keyword::Between, Keyword::Like]
+                    .into_iter()
+                    .any(|k| self.peek_next_keyword(k)) =>
+            {
+                Some(4)
+            }
+            Some(
+                Token::Equal
+                | Token::NotEqual
+                | Token::LessThan
+                | Token::LessThanOrEqual
+                | Token::GreaterThan
+                | Token::GreaterThanOrEqual,
+            ) => Some(4),
+            Some(Token::Plus | Token::Minus) => Some(5),
+            Some(Token::Asterisk | Token::Slash | Token::Percent) => Some(6),
+            _ => None,
+        }
+    }
+
+    /// Parses the operator at the current token and its right operand.
+    fn parse_infix_expression(
+        &mut self,
+        left: Expression,
+        precedence: u8,
+    ) -> Result<Expression, ParseError> {
+        if self.consume_keyword(Keyword::Or) {
+            let right = self.parse_binary_expression(precedence + 1)?;
+            return Ok(Expression::Or(Box::new(left), Box::new(right)));
+        }
+        if self.consume_keyword(Keyword::And) {
+            let right = self.parse_binary_expression(precedence + 1)?;
+            return Ok(Expression::And(Box::new(left), Box::new(right)));
+        }
+
+        let negated = self.consume_keyword(Keyword::Not);
+        if self.consume_keyword(Keyword::In) {
+            return self.parse_in(left, negated);
+        }
+        if self.consume_keyword(Keyword::Between) {
+            // The bounds bind tighter than AND, so the AND here is BETWEEN's
+            let low = self.parse_binary_expression(precedence + 1)?;
+            self.expect_keyword(Keyword::And)?;
+            let high = self.parse_binary_expression(precedence + 1)?;
+            return Ok(Expression::Between {
+                expression: Box::new(left),
+                low: Box::new(low),
+                high: Box::new(high),
+                negated,
+            });
+        }
+        if self.consume_keyword(Keyword::Like) {
+            let pattern = self.parse_binary_expression(precedence + 1)?;
+            let escape = if self.consume_keyword(Keyword::Escape) {
+                Some(Box::new(self.parse_binary_expression(precedence + 1)?))
+            } else {
+                None
+            };
+            return Ok(Expression::Like {
+                expression: Box::new(left),
+                pattern: Box::new(pattern),
+                escape,
+                negated,
+            });
+        }
+
+        let operator = if self.consume_keyword(Keyword::Is) {
+            let not = self.consume_keyword(Keyword::Not);
+            if self.consume_token(&Token::Null) {
+                return Ok(Expression::IsNull {
+                    expression: Box::new(left),
+                    negated: not,
+                });
+            }
+            let operator = if not {
+                BinaryOperator::IsNotDistinctFrom
+            } else {
+                BinaryOperator::IsDistinctFrom
+            };
+            self.expect_keyword(Keyword::Distinct)?;
+            self.expect_keyword(Keyword::From)?;
+            operator
+        } else {
+            let operator = match self.current_token {
+                Some(Token::Equal) => BinaryOperator::Equal,
+                Some(Token::NotEqual) => BinaryOperator::NotEqual,
+                Some(Token::LessThan) => BinaryOperator::LessThan,
+                Some(Token::LessThanOrEqual) => BinaryOperator::LessThanOrEqual,
+                Some(Token::GreaterThan) => BinaryOperator::GreaterThan,
+                Some(Token::GreaterThanOrEqual) => BinaryOperator::GreaterThanOrEqual,
+                Some(Token::Plus) => BinaryOperator::Add,
+                Some(Token::Minus) => BinaryOperator::Subtract,
+                Some(Token::Asterisk) => BinaryOperator::Multiply,
+                Some(Token::Slash) => BinaryOperator::Divide,
+                Some(Token::Percent) => BinaryOperator::Modulo,
+                _ => return Err(self.error("I was expecting an operator.")),
+            };
+            self.next_token();
+            operator
+        };
+        let right = self.parse_binary_expression(precedence + 1)?;
+        Ok(Expression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        })
+    }
+
+    /// Parses a term with any prefix operators. NOT applies to a whole
+    /// comparison, while unary plus and minus bind tighter than any infix
+    /// operator.
+    fn parse_prefix_expression(&mut self) -> Result<Expression, ParseError> {
+        self.nested(Self::parse_prefix_operators)
+    }
+
+    fn parse_prefix_operators(&mut self) -> Result<Expression, ParseError> {
+        if self.consume_keyword(Keyword::Not) {
+            let expr = self.parse_binary_expression(3)?;
+            return Ok(Expression::Not(Box::new(expr)));
+        }
+        let operator = if self.consume_token(&Token::Minus) {
+            if self.consume_i64_min_magnitude() {
+                return Ok(Expression::Integer(i64::MIN));
+            }
+            UnaryOperator::Minus
+        } else if self.consume_token(&Token::Plus) {
+            UnaryOperator::Plus
+        } else {
+            return self.parse_collated_term();
+        };
+        let expr = self.parse_prefix_expression()?;
+        Ok(Expression::Unary {
+            operator,
+            expression: Box::new(expr),
+        })
+    }
+
+    /// Parses a literal, possibly signed. Only PRAGMA values are plain
+    /// literals; VALUES rows and every other clause take expressions.
+    fn parse_value(&mut self) -> Result<Value, ParseError> {
+        // Signs are folded in a loop rather than by recursion, so a long
+        // run of them cannot overflow the stack
+        let mut signed = false;
+        let mut negative = false;
+        loop {
+            if self.consume_token(&Token::Minus) {
+                negative = !negative;
+            } else if !self.consume_token(&Token::Plus) {
+                break;
+            }
+            signed = true;
+        }
+        if negative && self.consume_i64_min_magnitude() {
+            return Ok(Value::Integer(i64::MIN));
+        }
+        let value = self.parse_unsigned_value()?;
+        if !signed {
+            return Ok(value);
+        }
+        match value {
+            // Only i64::MIN, from a hex literal, has no negation; as in
+            // SQLite it becomes a float
+            Value::Integer(i) if negative => Ok(i
+                .checked_neg()
+                .map_or(Value::Float(-(i as f64)), Value::Integer)),
+            Value::Float(f) if negative => Ok(Value::Float(-f)),
+            Value::Integer(_) | Value::Float(_) => Ok(value),
+            _ => Err(self.error("A sign must be followed by a number.")),
+        }
+    }
+
+    fn parse_unsigned_value(&mut self) -> Result<Value, ParseError> {
+        match self.current_token.clone() {
+            Some(Token::Integer(i)) => {
+                self.next_token();
+                Ok(Value::Integer(i))
+            }
+            Some(Token::Float(f)) => {
+                self.next_token();
+                Ok(Value::Float(f))
+            }
+            Some(Token::StringLiteral(ref s)) => {
+                self.next_token();
+                Ok(Value::Text(s.to_string()))
+            }
+            Some(Token::Blob(bytes)) => {
+                self.next_token();
+                Ok(Value::Blob(bytes))
+            }
+            Some(Token::Null) => {
+                self.next_token();
+                Ok(Value::Null)
+            }
+            Some(Token::Boolean(b)) => {
+                self.next_token();
+                Ok(Value::Boolean(b))
+            }
+            Some(Token::Parameter(ref text)) => {
+                let parameter = self.parameter(text)?;
+                self.next_token();
+                Ok(Value::Parameter(parameter))
+            }
+            Some(Token::Unknown(c)) => Err(self.error(format!("'{}' is not valid here.", c))),
+            _ => Err(self.error("This is an unexpected token.")),
+        }
+    }
+
+    /// Consumes `9223372036854775808` following a minus sign. It lexes as
+    /// out of range, since it does not fit in an i64, but negated it is
+    /// `i64::MIN`.
+    fn consume_i64_min_magnitude(&mut self) -> bool {
+        let (start, end) = self.current_span;
+        if matches!(self.current_token, Some(Token::Error(_)))
+            && &self.input[start..end] == "9223372036854775808"
+        {
+            self.next_token();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Interprets the text of a bind parameter token.
+    fn parameter(&self, text: &str) -> Result<Parameter, ParseError> {
+        match text.strip_prefix('?') {
+            Some("") => Ok(Parameter::Next),
+            Some(number) => match number.parse::<u32>() {
+                Ok(number) if number > 0 => Ok(Parameter::Numbered(number)),
+                _ => Err(self.error(format!("'{}' is not a valid parameter number.", text))),
+            },
+            None => Ok(Parameter::Named(text.to_string())),
+        }
+    }
+
+    /// Parses a term followed by any number of `COLLATE name` suffixes.
+    fn parse_collated_term(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_term()?;
+        while self.consume_keyword(Keyword::Collate) {
+            if let Some(Token::Identifier(ref name)) = self.current_token {
+                expr = Expression::Collate {
+                    expression: Box::new(expr),
+                    collation: name.to_uppercase(),
+                };
+                self.next_token();
+            } else {
+                return Err(self.error("I was expecting a collation name."));
+            }
+        }
+        Ok(expr)
+    }
+
+    /// Parses the parenthesized list or subquery following `[NOT] IN`.
+    fn parse_in(&mut self, left: Expression, negated: bool) -> Result<Expression, ParseError> {
+        self.expect_token(&Token::LeftParen)?;
+        if self.peek_keyword(Keyword::Select) {
+            let subquery = self.parse_select_inner()?;
+            self.expect_token(&Token::RightParen)?;
+            return Ok(Expression::InSubquery {
+                expression: Box::new(left),
+                subquery: Box::new(subquery),
+                negated,
+            });
+        }
+        // As in SQLite, an empty list is allowed and matches nothing
+        let list = if self.current_token == Some(Token::RightParen) {
+            Vec::new()
+        } else {
+            self.parse_expression_list()?
+        };
+        self.expect_token(&Token::RightParen)?;
+        Ok(Expression::InList {
+            expression: Box::new(left),
+            list,
+            negated,
+        })
+    }
+
+    /// Parses the rest of a `CASE` expression, after `CASE`.
+    fn parse_case(&mut self) -> Result<Expression, ParseError> {
+        let operand = if self.peek_keyword(Keyword::When) {
+            None
+        } else {
+            Some(Box::new(self.parse_expression()?))
+        };
+        let mut branches = Vec::new();
+        while self.consume_keyword(Keyword::When) {
+            let condition = self.parse_expression()?;
+            self.expect_keyword(Keyword::Then)?;
+            branches.push((condition, self.parse_expression()?));
+        }
+        if branches.is_empty() {
+            return Err(self.expected(Keyword::When));
+        }
+        let else_result = if self.consume_keyword(Keyword::Else) {
+            Some(Box::new(self.parse_expression()?))
+        } else {
+            None
+        };
+        self.expect_keyword(Keyword::End)?;
+        Ok(Expression::Case {
+            operand,
+            branches,
+            else_result,
+        })
+    }
+
+    fn parse_term(&mut self) -> Result<Expression, ParseError> {
+        match self.current_token.clone() {
+            Some(Token::LeftParen) => {
+                self.next_token();
+                if self.peek_keyword(Keyword::Select) {
+                    let subquery = self.parse_select_inner()?;
+                    self.expect_token(&Token::RightParen)?;
+                    return Ok(Expression::Subquery(Box::new(subquery)));
+                }
+                let expr = self.parse_expression()?;
+                if self.consume_token(&Token::Comma) {
+                    // A row value such as `(a, b)`, compared element-wise
+                    let mut elements = vec![expr];
+                    elements.extend(self.parse_expression_list()?);
+                    self.expect_token(&Token::RightParen)?;
+                    Ok(Expression::Row(elements))
+                } else {
+                    self.expect_token(&Token::RightParen)?;
+                    Ok(expr)
+                }
+            }
+            Some(Token::Integer(i)) => {
+                self.next_token();
+                Ok(Expression::Integer(i))
+            }
+            Some(Token::Float(f)) => {
+                self.next_token();
+                Ok(Expression::Float(f))
+            }
+            Some(Token::StringLiteral(ref s)) => {
+                self.next_token();
+                Ok(Expression::Text(s.to_string()))
+            }
+            Some(Token::Blob(bytes)) => {
+                self.next_token();
+                Ok(Expression::Blob(bytes))
+            }
+            Some(Token::Null) => {
+                self.next_token();
+                Ok(Expression::Null)
+            }
+            Some(Token::Boolean(b)) => {
+                self.next_token();
+                Ok(Expression::Boolean(b))
+            }
+            Some(Token::Asterisk) => {
+                self.next_token();
+                Ok(Expression::Asterisk)
+            }
+            Some(Token::Parameter(ref text)) => {
+                let parameter = self.parameter(text)?;
+                self.next_token();
+                Ok(Expression::Parameter(parameter))
+            }
+            Some(Token::Variable(ref name)) => {
+                self.next_token();
+                Ok(Expression::Variable(name.to_string()))
+            }
+            Some(Token::Keyword(Keyword::CurrentTimestamp)) => {
+                self.next_token();
+                Ok(Expression::CurrentTime(CurrentTime::Timestamp))
+            }
+            Some(Token::Keyword(Keyword::CurrentDate)) => {
+                self.next_token();
+                Ok(Expression::CurrentTime(CurrentTime::Date))
+            }
+            Some(Token::Keyword(Keyword::CurrentTime)) => {
+                self.next_token();
+                Ok(Expression::CurrentTime(CurrentTime::Time))
+            }
+            Some(Token::Keyword(Keyword::Cast)) => {
+                self.next_token();
+                self.expect_token(&Token::LeftParen)?;
+                let expression = self.parse_expression()?;
+                self.expect_keyword(Keyword::As)?;
+                let Some(data_type) = self.parse_type_name()? else {
+                    return Err(self.error("A type name is required in CAST."));
+                };
+                self.expect_token(&Token::RightParen)?;
+                Ok(Expression::Cast {
+                    expression: Box::new(expression),
+                    data_type,
+                })
+            }
+            Some(Token::Keyword(Keyword::Case)) => {
+                self.next_token();
+                self.parse_case()
+            }
+            Some(Token::Keyword(Keyword::Exists)) => {
+                self.next_token();
+                self.expect_token(&Token::LeftParen)?;
+                let subquery = self.parse_select_inner()?;
+                self.expect_token(&Token::RightParen)?;
+                Ok(Expression::Exists(Box::new(subquery)))
+            }
+            Some(Token::Unknown(c)) => Err(self.error(format!("'{}' is not valid here.", c))),
+            _ => {
+                let Some(identifier) = self.consume_name() else {
+                    return Err(self.error("This is an unexpected token."));
+                };
+                if self.consume_token(&Token::Dot) {
+                    if let Some(field) = self.consume_name() {
+                        Ok(Expression::Identifier(format!("{}.{}", identifier, field)))
+                    } else {
+                        Err(self.error("I was expecting a field name."))
+                    }
+                } else if self.consume_token(&Token::LeftParen) {
+                    let distinct = self.parse_distinct();
+                    let mut args = Vec::new();
+                    if !self.consume_token(&Token::RightParen) {
+                        loop {
+                            let expr = self.parse_expression()?;
+                            args.push(expr);
+                            if self.consume_token(&Token::Comma) {
+                                continue;
+                            } else {
+                                self.expect_token(&Token::RightParen)?;
+                                break;
+                            }
+                        }
+                    }
+                    Ok(Expression::Function {
+                        name: identifier,
+                        arguments: args,
+                        distinct,
+                    })
+                } else {
+                    Ok(Expression::Identifier(identifier))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Query {
+        Parser::new(sql)
+            .and_then(|mut parser| parser.parse())
+            .expect("Failed to parse query")
+    }
+
+    /// Tests that a self-join keeps a separate scope for each alias.
+    #[test]
+    fn test_self_join_aliases() {
+        let query = parse("SELECT e1.name FROM emp e1 JOIN emp AS e2 ON e1.manager = e2.id");
+        let Query::Select(select) = query else {
+            panic!("Expected a SELECT query");
+        };
+        assert_eq!(select.table.scope_name(), Some("e1"));
+        assert_eq!(select.joins.len(), 1);
+        assert_eq!(select.joins[0].table.scope_name(), Some("e2"));
+    }
+
+    /// Tests a subquery in FROM with an alias and column renaming.
+    #[test]
+    fn test_derived_table() {
+        let query = parse("SELECT sub.a FROM (SELECT x, y FROM t WHERE x > 1) AS sub(a, b)");
+        let Query::Select(select) = query else {
+            panic!("Expected a SELECT query");
+        };
+        let TableReference::Derived(derived) = select.table else {
+            panic!("Expected a derived table");
+        };
+        assert_eq!(derived.alias.as_deref(), Some("sub"));
+        assert_eq!(derived.columns, vec!["a", "b"]);
+        assert_eq!(derived.subquery.columns.len(), 2);
+        assert!(derived.subquery.where_clause.is_some());
+    }
+
+    /// Tests TRUNCATE with and without the optional TABLE keyword.
+    #[test]
+    fn test_truncate() {
+        for sql in ["TRUNCATE TABLE logs", "TRUNCATE logs"] {
+            let Query::Truncate(table) = parse(sql) else {
+                panic!("Expected a TRUNCATE query");
+            };
+            assert_eq!(table.name, "logs");
+        }
+    }
+
+    /// Tests a MERGE with matched update, matched delete and unmatched insert.
+    #[test]
+    fn test_merge() {
+        let query = parse(
+            "MERGE INTO stock s USING deliveries d ON s.item = d.item \
+             WHEN MATCHED AND d.qty = 0 THEN DELETE \
+             WHEN MATCHED THEN UPDATE SET qty = d.qty \
+             WHEN NOT MATCHED THEN INSERT (item, qty) VALUES (d.item, d.qty)",
+        );
+        let Query::Merge(merge) = query else {
+            panic!("Expected a MERGE query");
+        };
+        assert_eq!(merge.target.scope_name(), "s");
+        assert_eq!(merge.source.scope_name(), Some("d"));
+        assert_eq!(merge.clauses.len(), 3);
+        assert!(merge.clauses[0].matched && merge.clauses[0].condition.is_some());
+        assert!(matches!(merge.clauses[0].action, MergeAction::Delete));
+        assert!(matches!(&merge.clauses[1].action, MergeAction::Update(a) if a[0].column == "qty"));
+        assert!(!merge.clauses[2].matched);
+        assert!(matches!(
+            &merge.clauses[2].action,
+            MergeAction::Insert { columns, values } if columns.len() == 2 && values.len() == 2
+        ));
+
+        assert!(Parser::new(
+            "MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN INSERT VALUES (1)"
+        )
+        .and_then(|mut parser| parser.parse())
+        .is_err());
+    }
+
+    /// Tests UPDATE ... FROM and DELETE ... USING join sources.
+    #[test]
+    fn test_join_based_dml() {
+        let Query::Update(update) =
+            parse("UPDATE t SET total = o.total FROM orders o WHERE t.id = o.id")
+        else {
+            panic!("Expected an UPDATE query");
+        };
+        assert_eq!(update.assignments.len(), 1);
+        assert_eq!(update.from.unwrap().scope_name(), Some("o"));
+        assert!(update.where_clause.is_some());
+
+        let Query::Delete(delete) =
+            parse("DELETE FROM t USING banned b JOIN users u ON b.uid = u.id WHERE t.uid = u.id")
+        else {
+            panic!("Expected a DELETE query");
+        };
+        assert_eq!(delete.table.name, "t");
+        assert_eq!(delete.using.unwrap().scope_name(), Some("b"));
+        assert_eq!(delete.joins.len(), 1);
+        assert!(delete.where_clause.is_some());
+    }
+
+    /// Tests resolving ORDER BY ordinals, aliases and plain expressions.
+    #[test]
+    fn test_order_by_resolution() {
+        let Query::Select(select) =
+            parse("SELECT name, salary AS pay FROM emp ORDER BY 2 DESC, pay, name, dept")
+        else {
+            panic!("Expected a SELECT query");
+        };
+        let order_by = select.order_by.as_ref().unwrap();
+        let resolved: Vec<_> = order_by
+            .iter()
+            .map(|ordering| select.resolve_order_key(&ordering.expression).unwrap())
+            .collect();
+        assert!(matches!(resolved[0], Expression::Identifier(name) if name == "salary"));
+        assert!(matches!(resolved[1], Expression::Identifier(name) if name == "salary"));
+        assert!(matches!(resolved[2], Expression::Identifier(name) if name == "name"));
+        assert!(matches!(resolved[3], Expression::Identifier(name) if name == "dept"));
+        assert!(matches!(order_by[0].direction, SortOrder::Descending));
+
+        assert!(select.resolve_order_key(&Expression::Integer(3)).is_err());
+        assert!(select.resolve_order_key(&Expression::Integer(0)).is_err());
+    }
+
+    /// Tests explicit NULLS FIRST / NULLS LAST and the per-direction default.
+    #[test]
+    fn test_nulls_ordering() {
+        let Query::Select(select) = parse(
+            "SELECT first FROM people ORDER BY first NULLS LAST, age DESC NULLS FIRST, a, b DESC",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let order_by = select.order_by.unwrap();
+        assert_eq!(order_by[0].nulls, Some(NullsOrder::Last));
+        assert_eq!(order_by[1].nulls_order(), NullsOrder::First);
+        assert_eq!(order_by[2].nulls, None);
+        assert_eq!(order_by[2].nulls_order(), NullsOrder::First);
+        assert_eq!(order_by[3].nulls_order(), NullsOrder::Last);
+    }
+
+    /// Tests row-value comparisons used for keyset pagination.
+    #[test]
+    fn test_row_value_comparison() {
+        let Query::Select(select) = parse(
+            "SELECT id FROM posts WHERE (created, id) > ('2024-01-01', 42) AND (score) = 3 ORDER BY created, id",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let Some(Expression::And(left, right)) = select.where_clause else {
+            panic!("Expected an AND expression");
+        };
+        let Expression::Binary {
+            left: row,
+            operator: BinaryOperator::GreaterThan,
+            right: bound,
+        } = *left
+        else {
+            panic!("Expected a row comparison");
+        };
+        assert!(matches!(*row, Expression::Row(ref elements) if elements.len() == 2));
+        assert!(matches!(*bound, Expression::Row(ref elements) if elements.len() == 2));
+        assert!(matches!(
+            *right,
+            Expression::Binary { left, .. } if matches!(*left, Expression::Identifier(_))
+        ));
+    }
+
+    /// Tests the null-safe IS [NOT] DISTINCT FROM comparisons.
+    #[test]
+    fn test_is_distinct_from() {
+        let Query::Select(select) = parse(
+            "SELECT a.id FROM a JOIN b ON a.key IS NOT DISTINCT FROM b.key WHERE a.x IS DISTINCT FROM 1",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(
+            select.joins[0].condition,
+            Some(Expression::Binary {
+                operator: BinaryOperator::IsNotDistinctFrom,
+                ..
+            })
+        ));
+        assert!(matches!(
+            select.where_clause,
+            Some(Expression::Binary {
+                operator: BinaryOperator::IsDistinctFrom,
+                ..
+            })
+        ));
+    }
+
+    /// Tests COLLATE in comparisons and ORDER BY keys.
+    #[test]
+    fn test_collate() {
+        let Query::Select(select) = parse(
+            "SELECT name FROM users WHERE name = 'bob' COLLATE nocase ORDER BY name COLLATE NOCASE DESC",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let Some(Expression::Binary { right, .. }) = select.where_clause else {
+            panic!("Expected a comparison");
+        };
+        assert!(
+            matches!(*right, Expression::Collate { ref collation, .. } if collation == "NOCASE")
+        );
+        let order_by = select.order_by.unwrap();
+        assert!(matches!(order_by[0].expression, Expression::Collate { .. }));
+        assert!(matches!(order_by[0].direction, SortOrder::Descending));
+    }
+
+    /// Tests CREATE TEMP VIEW, view column lists and DROP VIEW IF EXISTS.
+    #[test]
+    fn test_temp_views() {
+        let Query::CreateView(view) =
+            parse("CREATE TEMP VIEW IF NOT EXISTS recent AS SELECT id FROM posts WHERE id > 100")
+        else {
+            panic!("Expected a CREATE VIEW query");
+        };
+        assert_eq!(view.name, "recent");
+        assert!(view.temporary && view.if_not_exists);
+        assert!(view.query.where_clause.is_some());
+
+        assert!(view.columns.is_empty());
+
+        let Query::CreateView(view) =
+            parse("CREATE VIEW everything (post_id, post_title) AS SELECT id, title FROM posts")
+        else {
+            panic!("Expected a CREATE VIEW query");
+        };
+        assert!(!view.temporary && !view.if_not_exists);
+        assert_eq!(view.columns, ["post_id", "post_title"]);
+
+        let Query::DropView(drop) = parse("DROP VIEW IF EXISTS recent") else {
+            panic!("Expected a DROP VIEW query");
+        };
+        assert_eq!(drop.name, "recent");
+        assert!(drop.if_exists);
+    }
+
+    /// Tests time-travel table references with and without an alias.
+    #[test]
+    fn test_as_of() {
+        let Query::Select(select) = parse(
+            "SELECT o.total FROM orders AS OF TIMESTAMP '2024-06-01 12:00:00' o JOIN items AS OF TRANSACTION 17 ON o.id = items.order_id",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let TableReference::Table(orders) = select.table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(orders.scope_name(), "o");
+        assert!(matches!(
+            orders.as_of,
+            Some(AsOf::Timestamp(Expression::Text(_)))
+        ));
+        let TableReference::Table(ref items) = select.joins[0].table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(items.alias, None);
+        assert!(matches!(
+            items.as_of,
+            Some(AsOf::Transaction(Expression::Integer(17)))
+        ));
+    }
+
+    /// Tests GRANT and REVOKE privilege lists.
+    #[test]
+    fn test_grant_revoke() {
+        let Query::Grant(grant) = parse("GRANT SELECT, INSERT ON TABLE orders TO alice, reporting")
+        else {
+            panic!("Expected a GRANT query");
+        };
+        assert_eq!(grant.privileges, vec![Privilege::Select, Privilege::Insert]);
+        assert_eq!(grant.table, "orders");
+        assert_eq!(grant.users, vec!["alice", "reporting"]);
+
+        let Query::Revoke(revoke) = parse("REVOKE ALL PRIVILEGES ON orders FROM alice") else {
+            panic!("Expected a REVOKE query");
+        };
+        assert_eq!(revoke.privileges, vec![Privilege::All]);
+        assert_eq!(revoke.users, vec!["alice"]);
+    }
+
+    /// Tests creating, refreshing and dropping a materialized view.
+    #[test]
+    fn test_materialized_views() {
+        let Query::CreateView(view) = parse(
+            "CREATE MATERIALIZED VIEW daily AS SELECT day, SUM(total) FROM orders GROUP BY day",
+        ) else {
+            panic!("Expected a CREATE VIEW query");
+        };
+        assert!(view.materialized && !view.temporary);
+        assert!(view.query.group_by.is_some());
+
+        let Query::RefreshMaterializedView(name) = parse("REFRESH MATERIALIZED VIEW daily") else {
+            panic!("Expected a REFRESH query");
+        };
+        assert_eq!(name, "daily");
+
+        let Query::DropView(drop) = parse("DROP MATERIALIZED VIEW daily") else {
+            panic!("Expected a DROP VIEW query");
+        };
+        assert!(drop.materialized && !drop.if_exists);
+    }
+
+    /// Tests a plain SELECT with a projection list, FROM table and WHERE.
+    #[test]
+    fn test_select() {
+        let Query::Select(select) = parse("SELECT id, name FROM users WHERE id = 1") else {
+            panic!("Expected a SELECT query");
+        };
+        assert_eq!(select.columns.len(), 2);
+        assert!(
+            matches!(select.columns[1].expression, Expression::Identifier(ref name) if name == "name")
+        );
+        assert_eq!(select.table.scope_name(), Some("users"));
+        assert!(matches!(
+            select.where_clause,
+            Some(Expression::Binary {
+                operator: BinaryOperator::Equal,
+                ..
+            })
+        ));
+
+        let Query::Select(select) = parse("SELECT * FROM users") else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(select.columns[0].expression, Expression::Asterisk));
+        assert!(select.where_clause.is_none());
+    }
+
+    /// Tests UPDATE with several assignments, with and without WHERE.
+    #[test]
+    fn test_update() {
+        let Query::Update(update) =
+            parse("UPDATE users SET name = 'bob', active = TRUE WHERE id = 7")
+        else {
+            panic!("Expected an UPDATE query");
+        };
+        assert_eq!(update.table.name, "users");
+        assert_eq!(update.assignments.len(), 2);
+        assert_eq!(update.assignments[0].column, "name");
+        assert!(matches!(
+            update.assignments[1].value,
+            Expression::Boolean(true)
+        ));
+        assert!(update.from.is_none() && update.where_clause.is_some());
+
+        let Query::Update(update) = parse("UPDATE users SET visits = 0") else {
+            panic!("Expected an UPDATE query");
+        };
+        assert!(update.where_clause.is_none());
+    }
+
+    /// Tests table-valued function calls in FROM and JOIN.
+    #[test]
+    fn test_table_functions() {
+        let Query::Select(select) = parse(
+            "SELECT value FROM generate_series(1, 100) JOIN json_each(doc) AS j ON value = j.key",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let TableReference::Function(ref series) = select.table else {
+            panic!("Expected a table function");
+        };
+        assert_eq!(series.name, "generate_series");
+        assert_eq!(series.arguments.len(), 2);
+        assert_eq!(select.table.scope_name(), Some("generate_series"));
+        assert_eq!(select.joins[0].table.scope_name(), Some("j"));
+    }
+
+    /// Tests DELETE with and without WHERE.
+    #[test]
+    fn test_delete() {
+        let Query::Delete(delete) = parse("DELETE FROM sessions WHERE expired = TRUE") else {
+            panic!("Expected a DELETE query");
+        };
+        assert_eq!(delete.table.name, "sessions");
+        assert!(delete.using.is_none() && delete.where_clause.is_some());
+
+        let Query::Delete(delete) = parse("DELETE FROM sessions") else {
+            panic!("Expected a DELETE query");
+        };
+        assert!(delete.where_clause.is_none());
+    }
+
+    /// Tests RETURNING on INSERT, UPDATE and DELETE.
+    #[test]
+    fn test_returning() {
+        let Query::Insert(insert) =
+            parse("INSERT INTO users (name) VALUES ('a') RETURNING id, name AS n")
+        else {
+            panic!("Expected an INSERT query");
+        };
+        assert_eq!(insert.returning.len(), 2);
+        assert_eq!(insert.returning[1].alias.as_deref(), Some("n"));
+
+        let Query::Update(update) = parse("UPDATE users SET age = age + 1 RETURNING *") else {
+            panic!("Expected an UPDATE query");
+        };
+        assert!(matches!(
+            update.returning[0].expression,
+            Expression::Asterisk
+        ));
+
+        let Query::Delete(delete) = parse("DELETE FROM sessions WHERE expired = TRUE RETURNING id")
+        else {
+            panic!("Expected a DELETE query");
+        };
+        assert_eq!(delete.returning.len(), 1);
+
+        let Query::Insert(insert) = parse("INSERT INTO users DEFAULT VALUES RETURNING id") else {
+            panic!("Expected an INSERT query");
+        };
+        assert!(insert.default_values && insert.returning.len() == 1);
+    }
+
+    /// Tests setting and reading session variables and the current time.
+    #[test]
+    fn test_session_variables() {
+        let Query::SetVariable { name, value } = parse("SET @tenant = 42") else {
+            panic!("Expected a SET query");
+        };
+        assert_eq!(name, "tenant");
+        assert!(matches!(value, Expression::Integer(42)));
+
+        let Query::Select(select) = parse(
+            "SELECT CURRENT_TIMESTAMP, current_date, current_user() FROM events WHERE tenant = @tenant",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(
+            select.columns[0].expression,
+            Expression::CurrentTime(CurrentTime::Timestamp)
+        ));
+        assert!(matches!(
+            select.columns[1].expression,
+            Expression::CurrentTime(CurrentTime::Date)
+        ));
+        assert!(
+            matches!(select.columns[2].expression, Expression::Function { ref name, .. } if name == "current_user")
+        );
+        let Some(Expression::Binary { right, .. }) = select.where_clause else {
+            panic!("Expected a comparison");
+        };
+        assert!(matches!(*right, Expression::Variable(ref name) if name == "tenant"));
+    }
+
+    /// Tests CREATE TABLE with typed and untyped columns.
+    #[test]
+    fn test_create_table() {
+        let Query::CreateTable(create) = parse(
+            "CREATE TABLE IF NOT EXISTS users (id INTEGER, name VARCHAR(255), score DOUBLE PRECISION, price DECIMAL(10, 2), extra)",
+        ) else {
+            panic!("Expected a CREATE TABLE query");
+        };
+        assert_eq!(create.name, "users");
+        assert!(create.if_not_exists && !create.temporary);
+        let types: Vec<_> = create
+            .columns
+            .iter()
+            .map(|column| column.data_type.as_deref())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                Some("INTEGER"),
+                Some("VARCHAR(255)"),
+                Some("DOUBLE PRECISION"),
+                Some("DECIMAL(10, 2)"),
+                None
+            ]
+        );
+        assert_eq!(create.columns[4].name, "extra");
+    }
+
+    /// Tests DROP TABLE with and without IF EXISTS.
+    #[test]
+    fn test_drop_table() {
+        let Query::DropTable(drop) = parse("DROP TABLE IF EXISTS users") else {
+            panic!("Expected a DROP TABLE query");
+        };
+        assert_eq!(drop.name, "users");
+        assert!(drop.if_exists);
+
+        let Query::DropTable(drop) = parse("DROP TABLE users") else {
+            panic!("Expected a DROP TABLE query");
+        };
+        assert!(!drop.if_exists);
+    }
+
+    /// Tests each ALTER TABLE operation.
+    #[test]
+    fn test_alter_table() {
+        let operation = |sql| {
+            let Query::AlterTable(alter) = parse(sql) else {
+                panic!("Expected an ALTER TABLE query");
+            };
+            assert_eq!(alter.name, "users");
+            alter.operation
+        };
+
+        assert!(matches!(
+            operation("ALTER TABLE users RENAME TO members"),
+            AlterTableOperation::RenameTable(name) if name == "members"
+        ));
+        assert!(matches!(
+            operation("ALTER TABLE users RENAME COLUMN name TO full_name"),
+            AlterTableOperation::RenameColumn { old_name, new_name }
+                if old_name == "name" && new_name == "full_name"
+        ));
+        assert!(matches!(
+            operation("ALTER TABLE users RENAME name TO full_name"),
+            AlterTableOperation::RenameColumn { .. }
+        ));
+        let AlterTableOperation::AddColumn(column) =
+            operation("ALTER TABLE users ADD COLUMN email VARCHAR(255)")
+        else {
+            panic!("Expected ADD COLUMN");
+        };
+        assert_eq!(column.name, "email");
+        assert_eq!(column.data_type.as_deref(), Some("VARCHAR(255)"));
+        assert!(matches!(
+            operation("ALTER TABLE users DROP COLUMN email"),
+            AlterTableOperation::DropColumn(name) if name == "email"
+        ));
+    }
+
+    /// Tests CREATE [UNIQUE] INDEX and DROP INDEX.
+    #[test]
+    fn test_index_ddl() {
+        let Query::CreateIndex(create) =
+            parse("CREATE UNIQUE INDEX IF NOT EXISTS idx_users ON users (last_name, age DESC)")
+        else {
+            panic!("Expected a CREATE INDEX query");
+        };
+        assert_eq!(create.name, "idx_users");
+        assert_eq!(create.table, "users");
+        assert!(create.unique && create.if_not_exists);
+        assert_eq!(create.columns.len(), 2);
+        assert_eq!(create.columns[0].name, "last_name");
+        assert!(matches!(create.columns[0].direction, SortOrder::Ascending));
+        assert!(matches!(create.columns[1].direction, SortOrder::Descending));
+
+        let Query::CreateIndex(create) = parse("CREATE INDEX idx_age ON users (age)") else {
+            panic!("Expected a CREATE INDEX query");
+        };
+        assert!(!create.unique && !create.if_not_exists);
+
+        let Query::DropIndex(drop) = parse("DROP INDEX IF EXISTS idx_users") else {
+            panic!("Expected a DROP INDEX query");
+        };
+        assert_eq!(drop.name, "idx_users");
+        assert!(drop.if_exists);
+    }
+
+    /// Tests operator precedence and associativity in expressions.
+    #[test]
+    fn test_expression_precedence() {
+        let Query::Select(select) =
+            parse("SELECT a + b * -c - d FROM t WHERE NOT x + 1 > 2 AND y = 1 OR z = 2")
+        else {
+            panic!("Expected a SELECT query");
+        };
+
+        // (a + (b * -c)) - d
+        let Expression::Binary {
+            left,
+            operator: BinaryOperator::Subtract,
+            right,
+        } = &select.columns[0].expression
+        else {
+            panic!("Expected subtraction at the top");
+        };
+        assert!(matches!(**right, Expression::Identifier(ref name) if name == "d"));
+        let Expression::Binary {
+            operator: BinaryOperator::Add,
+            right: product,
+            ..
+        } = &**left
+        else {
+            panic!("Expected addition on the left");
+        };
+        let Expression::Binary {
+            operator: BinaryOperator::Multiply,
+            right: negated,
+            ..
+        } = &**product
+        else {
+            panic!("Expected multiplication to bind tighter than addition");
+        };
+        assert!(matches!(
+            **negated,
+            Expression::Unary {
+                operator: UnaryOperator::Minus,
+                ..
+            }
+        ));
+
+        // ((NOT (x + 1 > 2)) AND y = 1) OR z = 2
+        let Some(Expression::Or(left, _)) = &select.where_clause else {
+            panic!("Expected OR at the top");
+        };
+        let Expression::And(not, _) = &**left else {
+            panic!("Expected AND under OR");
+        };
+        let Expression::Not(comparison) = &**not else {
+            panic!("Expected NOT under AND");
+        };
+        assert!(matches!(
+            **comparison,
+            Expression::Binary {
+                operator: BinaryOperator::GreaterThan,
+                ..
+            }
+        ));
+
+        // A NULL literal is not a column named "NULL"
+        let Query::Select(select) = parse("SELECT NULL, \"NULL\" FROM t") else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(select.columns[0].expression, Expression::Null));
+        assert!(matches!(
+            select.columns[1].expression,
+            Expression::Identifier(ref name) if name == "NULL"
+        ));
+    }
+
+    /// Tests INSERT with several rows in VALUES, and expressions in them.
+    #[test]
+    fn test_multi_row_insert() {
+        let Query::Insert(insert) = parse("INSERT INTO t (a, b) VALUES (1, 2), (3, 4), (5, 6)")
+        else {
+            panic!("Expected an INSERT query");
+        };
+        let rows = insert.values.unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == 2));
+        assert!(matches!(rows[2][1], Expression::Integer(6)));
+
+        let Query::Insert(insert) = parse("INSERT INTO t (a, b) VALUES (1 + 2, CURRENT_TIMESTAMP)")
+        else {
+            panic!("Expected an INSERT query");
+        };
+        let row = &insert.values.unwrap()[0];
+        assert!(matches!(
+            row[0],
+            Expression::Binary {
+                operator: BinaryOperator::Add,
+                ..
+            }
+        ));
+        assert!(matches!(
+            row[1],
+            Expression::CurrentTime(CurrentTime::Timestamp)
+        ));
+    }
+
+    /// Tests that parse_with_limits rejects input beyond each limit.
+    #[test]
+    fn test_parse_with_limits() {
+        let deep = format!(
+            "SELECT {}1{} FROM t",
+            "(".repeat(10_000),
+            ")".repeat(10_000)
+        );
+        let error = parse_with_limits(&deep, Limits::default()).unwrap_err();
+        assert!(error.message.contains("nested"));
+        let negated = format!("SELECT {}1 FROM t", "-".repeat(10_000));
+        assert!(parse_with_limits(&negated, Limits::default()).is_err());
+
+        // A literal's signs are folded without recursion
+        let signs = "- ".repeat(200_001);
+        let Query::Pragma(pragma) = parse(&format!("PRAGMA x = {}1", signs)) else {
+            panic!("Expected a PRAGMA");
+        };
+        assert!(matches!(pragma.value, Some(Value::Integer(-1))));
+        let signed_row = format!("INSERT INTO t (a) VALUES ({}5)", signs);
+        let _ = parse_with_limits(&signed_row, Limits::default());
+
+        let limits = Limits {
+            max_tokens: 4,
+            ..Limits::default()
+        };
+        let error = parse_with_limits("SELECT a, b, c FROM t", limits).unwrap_err();
+        assert!(error.message.contains("tokens"));
+        assert!(parse_with_limits("SELECT a FROM t", limits).is_ok());
+
+        let limits = Limits {
+            max_statement_len: 10,
+            ..Limits::default()
+        };
+        let error = parse_with_limits("SELECT a FROM t", limits).unwrap_err();
+        assert!(error.message.contains("bytes"));
+    }
+
+    /// Tests INSERT with a SELECT as its source.
+    #[test]
+    fn test_insert_select() {
+        let Query::Insert(insert) =
+            parse("INSERT INTO archive (id, name) SELECT id, name FROM users WHERE active = false")
+        else {
+            panic!("Expected an INSERT query");
+        };
+        assert_eq!(insert.table.name, "archive");
+        assert_eq!(insert.columns, vec!["id", "name"]);
+        assert!(insert.values.is_none());
+        let select = insert.select.unwrap();
+        assert_eq!(select.columns.len(), 2);
+        assert!(select.where_clause.is_some());
+    }
+
+    /// Tests INSERT without a column list and INSERT DEFAULT VALUES.
+    #[test]
+    fn test_insert_without_columns() {
+        let Query::Insert(insert) = parse("INSERT INTO t VALUES (1, 'a')") else {
+            panic!("Expected an INSERT query");
+        };
+        assert!(insert.columns.is_empty() && !insert.default_values);
+        assert_eq!(insert.values.unwrap()[0].len(), 2);
+
+        let Query::Insert(insert) = parse("INSERT INTO t SELECT * FROM s") else {
+            panic!("Expected an INSERT query");
+        };
+        assert!(insert.columns.is_empty() && insert.select.is_some());
+
+        let Query::Insert(insert) = parse("INSERT INTO t DEFAULT VALUES") else {
+            panic!("Expected an INSERT query");
+        };
+        assert_eq!(insert.table.name, "t");
+        assert!(insert.default_values);
+        assert!(insert.values.is_none() && insert.select.is_none());
+    }
+
+    /// Tests each form of bind parameter.
+    #[test]
+    fn test_bind_parameters() {
+        let Query::Select(select) = parse("SELECT ? FROM t WHERE a = ?2 AND b = :name OR c = $c")
+        else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(
+            select.columns[0].expression,
+            Expression::Parameter(Parameter::Next)
+        ));
+        let Some(Expression::Or(and, c)) = select.where_clause else {
+            panic!("Expected OR at the top");
+        };
+        let Expression::And(a, b) = *and else {
+            panic!("Expected AND under OR");
+        };
+        let parameter = |expr: Expression| match expr {
+            Expression::Binary { right, .. } => match *right {
+                Expression::Parameter(parameter) => parameter,
+                other => panic!("Expected a parameter, found {:?}", other),
+            },
+            other => panic!("Expected a comparison, found {:?}", other),
+        };
+        assert_eq!(parameter(*a), Parameter::Numbered(2));
+        assert_eq!(parameter(*b), Parameter::Named(":name".to_string()));
+        assert_eq!(parameter(*c), Parameter::Named("$c".to_string()));
+
+        let Query::Insert(insert) = parse("INSERT INTO t (a, b) VALUES (?, ?)") else {
+            panic!("Expected an INSERT query");
+        };
+        let rows = insert.values.unwrap();
+        assert!(matches!(rows[0][1], Expression::Parameter(Parameter::Next)));
+
+        assert!(Parser::new("SELECT ?0 FROM t").unwrap().parse().is_err());
+    }
+
+    /// Tests signed numbers in VALUES, in PRAGMA values and in expressions.
+    #[test]
+    fn test_signed_numbers() {
+        let Query::Insert(insert) = parse("INSERT INTO t (a, b, c) VALUES (-5, +1.5, -2.5)") else {
+            panic!("Expected an INSERT query");
+        };
+        let row = &insert.values.unwrap()[0];
+        assert!(matches!(
+            row[0],
+            Expression::Unary { operator: UnaryOperator::Minus, ref expression }
+                if matches!(**expression, Expression::Integer(5))
+        ));
+        assert!(matches!(
+            row[1],
+            Expression::Unary { operator: UnaryOperator::Plus, ref expression }
+                if matches!(**expression, Expression::Float(f) if f == 1.5)
+        ));
+        assert!(Parser::new("INSERT INTO t (a) VALUES (-)")
+            .unwrap()
+            .parse()
+            .is_err());
+
+        let Query::Insert(insert) = parse("INSERT INTO t (a) VALUES (-9223372036854775808)") else {
+            panic!("Expected an INSERT query");
+        };
+        let row = &insert.values.unwrap()[0];
+        assert!(matches!(row[0], Expression::Integer(i64::MIN)));
+        assert!(
+            Parser::new("INSERT INTO t (a) VALUES (9223372036854775808)")
+                .unwrap()
+                .parse()
+                .is_err()
+        );
+
+        let pragma_value = |sql| {
+            let Query::Pragma(pragma) = parse(sql) else {
+                panic!("Expected a PRAGMA");
+            };
+            pragma.value.unwrap()
+        };
+        assert!(matches!(pragma_value("PRAGMA x = -5"), Value::Integer(-5)));
+        assert!(matches!(pragma_value("PRAGMA x = -2.5"), Value::Float(f) if f == -2.5));
+        assert!(matches!(
+            pragma_value("PRAGMA x = -9223372036854775808"),
+            Value::Integer(i64::MIN)
+        ));
+        assert!(matches!(
+            pragma_value("PRAGMA x = -0x8000000000000000"),
+            Value::Float(f) if f == 9223372036854775808.0
+        ));
+        assert!(Parser::new("PRAGMA x = -'x'").unwrap().parse().is_err());
+
+        let Query::Select(select) = parse("SELECT -x FROM t WHERE y > -1") else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(
+            select.columns[0].expression,
+            Expression::Unary {
+                operator: UnaryOperator::Minus,
+                ..
+            }
+        ));
+    }
+
+    /// Tests INDEXED BY and NOT INDEXED hints.
+    #[test]
+    fn test_index_hints() {
+        let Query::Select(select) =
+            parse("SELECT * FROM users AS u INDEXED BY idx_age JOIN orders NOT INDEXED ON u.id = orders.user_id")
+        else {
+            panic!("Expected a SELECT query");
+        };
+        let TableReference::Table(users) = &select.table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(users.alias.as_deref(), Some("u"));
+        assert!(
+            matches!(users.index_hint, Some(IndexHint::IndexedBy(ref name)) if name == "idx_age")
+        );
+        let TableReference::Table(orders) = &select.joins[0].table else {
+            panic!("Expected a table");
+        };
+        assert!(matches!(orders.index_hint, Some(IndexHint::NotIndexed)));
+
+        let Query::Delete(delete) = parse("DELETE FROM users INDEXED BY idx_age WHERE age > 1")
+        else {
+            panic!("Expected a DELETE query");
+        };
+        assert!(delete.table.index_hint.is_some());
+    }
+
+    /// Tests blob literals in VALUES and in expressions.
+    #[test]
+    fn test_blob_literals() {
+        let Query::Insert(insert) = parse("INSERT INTO t (a, b) VALUES (X'ABcd', x'')") else {
+            panic!("Expected an INSERT query");
+        };
+        let row = &insert.values.unwrap()[0];
+        assert!(matches!(row[0], Expression::Blob(ref bytes) if bytes == &[0xab, 0xcd]));
+        assert!(matches!(row[1], Expression::Blob(ref bytes) if bytes.is_empty()));
+
+        let Query::Select(select) = parse("SELECT x FROM t WHERE x = X'00ff'") else {
+            panic!("Expected a SELECT query");
+        };
+        let Some(Expression::Binary { right, .. }) = select.where_clause else {
+            panic!("Expected a comparison");
+        };
+        assert!(matches!(*right, Expression::Blob(ref bytes) if bytes == &[0x00, 0xff]));
+
+        assert!(Parser::new("INSERT INTO t (a) VALUES (X'ABC')")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests schema-qualified table names.
+    #[test]
+    fn test_schema_qualified_tables() {
+        let Query::Select(select) =
+            parse("SELECT c.name FROM information_schema.columns AS c JOIN main.users ON c.table_name = 'users'")
+        else {
+            panic!("Expected a SELECT query");
+        };
+        let TableReference::Table(columns) = &select.table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(columns.schema.as_deref(), Some("information_schema"));
+        assert_eq!(columns.name, "columns");
+        assert_eq!(columns.scope_name(), "c");
+        let TableReference::Table(users) = &select.joins[0].table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(users.schema.as_deref(), Some("main"));
+        assert_eq!(users.scope_name(), "users");
+
+        let Query::Insert(insert) = parse("INSERT INTO main.users (id) VALUES (1)") else {
+            panic!("Expected an INSERT query");
+        };
+        assert_eq!(insert.table.schema.as_deref(), Some("main"));
+    }
+
+    /// Tests that errors point at the offending token.
+    #[test]
+    fn test_parse_error_position() {
+        let sql = "SELECT a\nFROM t\nWHERE (a = 1 FROM";
+        let error = Parser::new(sql).unwrap().parse().unwrap_err();
+        assert_eq!((error.line, error.column), (3, 14));
+        assert_eq!(error.offset, sql.rfind("FROM").unwrap());
+        assert_eq!(error.found.as_deref(), Some("FROM"));
+        assert_eq!(error.expected.as_deref(), Some(")"));
+        assert_eq!(
+            error.to_string(),
+            "Expected ')', but found 'FROM' (line 3, column 14)"
+        );
+
+        let error = Parser::new("INSERT INTO t (a) VALUES")
+            .unwrap()
+            .parse()
+            .unwrap_err();
+        assert_eq!(error.found, None);
+        assert_eq!(error.expected.as_deref(), Some("("));
+        assert_eq!(error.column, 25);
+    }
+
+    /// Tests parsing a script of several statements.
+    #[test]
+    fn test_parse_statements() {
+        let script = "CREATE TABLE users (id INTEGER, name TEXT);\n\
+                      ;\n\
+                      INSERT INTO users (id, name) VALUES (1, 'a;b');\n\
+                      -- done;\n\
+                      SELECT name FROM users";
+        let queries = Parser::new(script).unwrap().parse_statements().unwrap();
+        assert_eq!(queries.len(), 3);
+        assert!(matches!(queries[0], Query::CreateTable(_)));
+        assert!(matches!(queries[1], Query::Insert(_)));
+        assert!(matches!(queries[2], Query::Select(_)));
+
+        assert!(Parser::new("")
+            .unwrap()
+            .parse_statements()
+            .unwrap()
+            .is_empty());
+
+        let error = Parser::new("DROP TABLE a DROP TABLE b")
+            .unwrap()
+            .parse_statements()
+            .unwrap_err();
+        assert_eq!(error.expected.as_deref(), Some(";"));
+    }
+
+    /// Tests each kind of join and the USING constraint.
+    #[test]
+    fn test_join_kinds() {
+        let Query::Select(select) = parse(
+            "SELECT * FROM a INNER JOIN b ON a.id = b.id LEFT OUTER JOIN c USING (id, kind) \
+             RIGHT JOIN d ON a.id = d.id FULL JOIN e ON a.id = e.id CROSS JOIN f, g JOIN h USING (id)",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let kinds: Vec<_> = select.joins.iter().map(|join| join.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                JoinKind::Inner,
+                JoinKind::Left,
+                JoinKind::Right,
+                JoinKind::Full,
+                JoinKind::Cross,
+                JoinKind::Cross,
+                JoinKind::Inner,
+            ]
+        );
+        assert!(select.joins[0].condition.is_some());
+        assert_eq!(select.joins[1].using, vec!["id", "kind"]);
+        assert!(select.joins[1].condition.is_none());
+        assert_eq!(select.joins[5].table.scope_name(), Some("g"));
+        assert_eq!(select.joins[6].using, vec!["id"]);
+        assert!(select.joins.iter().all(|join| !join.natural));
+
+        let Query::Select(select) = parse("SELECT a FROM t1 NATURAL JOIN t2 NATURAL LEFT JOIN t3")
+        else {
+            panic!("Expected a SELECT query");
+        };
+        assert_eq!(select.table.scope_name(), Some("t1"));
+        let kinds: Vec<_> = select
+            .joins
+            .iter()
+            .map(|join| (join.kind, join.natural))
+            .collect();
+        assert_eq!(kinds, [(JoinKind::Inner, true), (JoinKind::Left, true)]);
+        for sql in [
+            "SELECT a FROM t1 NATURAL JOIN t2 ON t1.a = t2.a",
+            "SELECT a FROM t1 NATURAL t2",
+        ] {
+            assert!(Parser::new(sql).unwrap().parse().is_err(), "{}", sql);
+        }
+    }
+
+    /// Tests LIMIT and OFFSET in both of their forms.
+    #[test]
+    fn test_limit_offset() {
+        let Query::Select(select) =
+            parse("SELECT * FROM t ORDER BY a DESC NULLS LAST LIMIT 10 OFFSET 20")
+        else {
+            panic!("Expected a SELECT query");
+        };
+        assert_eq!(select.order_by.map(|o| o.len()), Some(1));
+        assert!(matches!(select.limit, Some(Expression::Integer(10))));
+        assert!(matches!(select.offset, Some(Expression::Integer(20))));
+
+        // SQLite's `LIMIT offset, count`
+        let Query::Select(select) = parse("SELECT * FROM t LIMIT 20, 10") else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(select.limit, Some(Expression::Integer(10))));
+        assert!(matches!(select.offset, Some(Expression::Integer(20))));
+
+        let Query::Select(select) = parse("SELECT * FROM t LIMIT :n") else {
+            panic!("Expected a SELECT query");
+        };
+        assert!(matches!(select.limit, Some(Expression::Parameter(_))));
+        assert!(select.offset.is_none());
+    }
+
+    /// Tests GROUP BY with several expressions and a HAVING predicate.
+    #[test]
+    fn test_group_by_having() {
+        let Query::Select(select) = parse(
+            "SELECT dept, year % 100, COUNT(*) FROM staff GROUP BY dept, year % 100 \
+             HAVING COUNT(*) > 1 AND MAX(salary) < 100 ORDER BY dept",
+        ) else {
+            panic!("Expected a SELECT query");
+        };
+        let group_by = select.group_by.unwrap();
+        assert_eq!(group_by.len(), 2);
+        assert!(matches!(
+            group_by[1],
+            Expression::Binary {
+                operator: BinaryOperator::Modulo,
+                ..
+            }
+        ));
+        assert!(matches!(select.having, Some(Expression::And(..))));
+        assert!(select.order_by.is_some());
+    }
+
+    /// Tests scalar, `EXISTS` and `IN` subqueries in expressions.
+    #[test]
+    fn test_subqueries() {
+        let Query::Select(select) = parse(
+            "SELECT (SELECT max(id) FROM users) FROM orders \
+             WHERE NOT EXISTS (SELECT * FROM users) AND user_id NOT IN (SELECT id FROM users)",
+        ) else {
+            panic!("Expected a SELECT");
+        };
+        assert!(matches!(
+            select.columns[0].expression,
+            Expression::Subquery(_)
+        ));
+        let Some(Expression::And(left, right)) = select.where_clause else {
+            panic!("Expected an AND condition");
+        };
+        assert!(matches!(*left, Expression::Not(ref e) if matches!(**e, Expression::Exists(_))));
+        assert!(matches!(
+            *right,
+            Expression::InSubquery { negated: true, .. }
+        ));
+    }
+
+    /// Tests simple and searched `CASE` expressions.
+    #[test]
+    fn test_case_expressions() {
+        let Query::Select(select) = parse(
+            "SELECT CASE status WHEN 1 THEN 'open' WHEN 2 THEN 'closed' END, \
+             CASE WHEN age >= 18 THEN 'adult' ELSE 'minor' END FROM users",
+        ) else {
+            panic!("Expected a SELECT");
+        };
+        let Expression::Case {
+            ref operand,
+            ref branches,
+            ref else_result,
+        } = select.columns[0].expression
+        else {
+            panic!("Expected a CASE expression");
+        };
+        assert!(operand.is_some());
+        assert_eq!(branches.len(), 2);
+        assert!(else_result.is_none());
+        assert!(matches!(
+            select.columns[1].expression,
+            Expression::Case {
+                operand: None,
+                else_result: Some(_),
+                ..
+            }
+        ));
+        assert!(Parser::new("SELECT CASE x END FROM t")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests BETWEEN, IN, LIKE and IS NULL, with their NOT forms.
+    #[test]
+    fn test_predicates() {
+        let Query::Select(select) = parse(
+            "SELECT * FROM users WHERE age NOT BETWEEN 18 AND 65 \
+             AND id IN (1, 2, 3) AND name LIKE 'a\\_%' ESCAPE '\\' \
+             AND email IS NOT NULL AND status NOT IN ()",
+        ) else {
+            panic!("Expected a SELECT");
+        };
+        let mut conditions = Vec::new();
+        let mut condition = select.where_clause.unwrap();
+        while let Expression::And(left, right) = condition {
+            conditions.push(*right);
+            condition = *left;
+        }
+        conditions.push(condition);
+        conditions.reverse();
+
+        assert_eq!(conditions.len(), 5);
+        assert!(matches!(
+            conditions[0],
+            Expression::Between { negated: true, .. }
+        ));
+        assert!(
+            matches!(conditions[1], Expression::InList { ref list, negated: false, .. } if list.len() == 3)
+        );
+        assert!(matches!(
+            conditions[2],
+            Expression::Like {
+                escape: Some(_),
+                negated: false,
+                ..
+            }
+        ));
+        assert!(matches!(
+            conditions[3],
+            Expression::IsNull { negated: true, .. }
+        ));
+        assert!(
+            matches!(conditions[4], Expression::InList { ref list, negated: true, .. } if list.is_empty())
+        );
+    }
+
+    /// Tests CAST with plain and sized type names.
+    #[test]
+    fn test_cast() {
+        let Query::Select(select) =
+            parse("SELECT CAST(price AS INTEGER), CAST(name AS VARCHAR(20)) FROM items")
+        else {
+            panic!("Expected a SELECT");
+        };
+        assert!(
+            matches!(select.columns[0].expression, Expression::Cast { ref data_type, .. } if data_type == "INTEGER")
+        );
+        assert!(
+            matches!(select.columns[1].expression, Expression::Cast { ref data_type, .. } if data_type == "VARCHAR(20)")
+        );
+        assert!(Parser::new("SELECT CAST(price AS) FROM items")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests TABLESAMPLE by percentage and by row count.
+    #[test]
+    fn test_table_sample() {
+        let Query::Select(select) =
+            parse("SELECT * FROM events e TABLESAMPLE (10 PERCENT) JOIN users TABLESAMPLE (100 ROWS) ON e.user_id = users.id")
+        else {
+            panic!("Expected a SELECT");
+        };
+        let TableReference::Table(ref events) = select.table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(events.alias.as_deref(), Some("e"));
+        let sample = events.sample.as_ref().unwrap();
+        assert_eq!(sample.unit, SampleUnit::Percent);
+        assert!(matches!(sample.size, Expression::Integer(10)));
+        let TableReference::Table(ref users) = select.joins[0].table else {
+            panic!("Expected a table");
+        };
+        assert_eq!(users.sample.as_ref().unwrap().unit, SampleUnit::Rows);
+        assert!(Parser::new("SELECT * FROM t TABLESAMPLE (10)")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests DISTINCT and ALL on SELECT and inside aggregate calls.
+    #[test]
+    fn test_distinct() {
+        let Query::Select(select) =
+            parse("SELECT DISTINCT city, count(DISTINCT name), sum(ALL age) FROM users")
+        else {
+            panic!("Expected a SELECT");
+        };
+        assert!(select.distinct);
+        assert!(matches!(
+            select.columns[1].expression,
+            Expression::Function { distinct: true, .. }
+        ));
+        assert!(matches!(
+            select.columns[2].expression,
+            Expression::Function {
+                distinct: false,
+                ..
+            }
+        ));
+
+        let Query::Select(select) = parse("SELECT ALL city FROM users") else {
+            panic!("Expected a SELECT");
+        };
+        assert!(!select.distinct);
+    }
+
+    /// Tests that compound selects form a left-to-right list, with ORDER BY
+    /// and LIMIT applying to the whole compound.
+    #[test]
+    fn test_compound_select() {
+        let Query::Select(select) = parse(
+            "SELECT id FROM users UNION ALL SELECT id FROM admins \
+             INTERSECT SELECT id FROM active EXCEPT SELECT id FROM banned ORDER BY id LIMIT 10",
+        ) else {
+            panic!("Expected a SELECT");
+        };
+        let operators: Vec<_> = select.compound.iter().map(|(op, _)| *op).collect();
+        assert_eq!(
+            operators,
+            [
+                SetOperator::UnionAll,
+                SetOperator::Intersect,
+                SetOperator::Except
+            ]
+        );
+        assert!(select.compound.iter().all(|(_, s)| s.order_by.is_none()));
+        assert!(select.order_by.is_some());
+        assert!(matches!(select.limit, Some(Expression::Integer(10))));
+    }
+
+    /// Tests that an unknown character is reported where it appears.
+    #[test]
+    fn test_unknown_character_error() {
+        let error = Parser::new("SELECT a FROM t WHERE a = ~1")
+            .unwrap()
+            .parse()
+            .unwrap_err();
+        assert_eq!(error.message, "'~' is not valid here.");
+        assert_eq!((error.line, error.column), (1, 27));
+    }
+
+    /// Tests WITH clauses on SELECT and INSERT.
+    #[test]
+    fn test_with_clause() {
+        let Query::With {
+            recursive,
+            ctes,
+            body,
+        } = parse(
+            "WITH RECURSIVE adults(id, name) AS (SELECT id, name FROM users WHERE age >= 18), \
+             recent AS (SELECT * FROM orders) SELECT * FROM adults JOIN recent ON adults.id = recent.user_id",
+        )
+        else {
+            panic!("Expected a WITH query");
+        };
+        assert!(recursive);
+        assert_eq!(ctes.len(), 2);
+        assert_eq!(ctes[0].name, "adults");
+        assert_eq!(ctes[0].columns, ["id", "name"]);
+        assert!(ctes[1].columns.is_empty());
+        assert!(matches!(*body, Query::Select(_)));
+
+        let Query::With { body, .. } = parse(
+            "WITH src AS (SELECT name FROM staging) INSERT INTO users (name) SELECT name FROM src",
+        ) else {
+            panic!("Expected a WITH query");
+        };
+        assert!(matches!(*body, Query::Insert(_)));
+        assert!(Parser::new("WITH src AS (SELECT a FROM t) DROP TABLE t")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests the default NULL sort position in each dialect.
+    #[test]
+    fn test_nulls_ordering_per_dialect() {
+        let Query::Select(select) = parse("SELECT a FROM t ORDER BY a, b DESC, c NULLS LAST")
+        else {
+            panic!("Expected a SELECT");
+        };
+        let order_by = select.order_by.unwrap();
+        for dialect in [Dialect::Sqlite, Dialect::MySql] {
+            assert_eq!(order_by[0].nulls_order_in(dialect), NullsOrder::First);
+            assert_eq!(order_by[1].nulls_order_in(dialect), NullsOrder::Last);
+        }
+        assert_eq!(order_by[0].nulls_order_in(Dialect::Ansi), NullsOrder::Last);
+        assert_eq!(order_by[1].nulls_order_in(Dialect::Ansi), NullsOrder::First);
+        assert_eq!(order_by[2].nulls_order_in(Dialect::Ansi), NullsOrder::Last);
+        assert_eq!(order_by[0].nulls_order(), NullsOrder::First);
+    }
+
+    /// Tests ON CONFLICT DO NOTHING and DO UPDATE on INSERT.
+    #[test]
+    fn test_upsert() {
+        let Query::Insert(insert) =
+            parse("INSERT INTO users (id, name) VALUES (1, 'a') ON CONFLICT DO NOTHING")
+        else {
+            panic!("Expected an INSERT");
+        };
+        let on_conflict = insert.on_conflict.unwrap();
+        assert!(on_conflict.target.is_empty());
+        assert!(matches!(on_conflict.action, ConflictAction::DoNothing));
+
+        let Query::Insert(insert) = parse(
+            "INSERT INTO counters (name, hits) VALUES ('home', 1) \
+             ON CONFLICT (name) DO UPDATE SET hits = hits + 1 WHERE hits < 100",
+        ) else {
+            panic!("Expected an INSERT");
+        };
+        let on_conflict = insert.on_conflict.unwrap();
+        assert_eq!(on_conflict.target, ["name"]);
+        let ConflictAction::DoUpdate {
+            assignments,
+            where_clause,
+        } = on_conflict.action
+        else {
+            panic!("Expected DO UPDATE");
+        };
+        assert_eq!(assignments[0].column, "hits");
+        assert!(where_clause.is_some());
+
+        // An ON without CONFLICT is left in place and rejected, not dropped
+        let error = Parser::new("INSERT INTO t (a) VALUES (1) ON x")
+            .unwrap()
+            .parse()
+            .unwrap_err();
+        assert_eq!(error.found.as_deref(), Some("ON"));
+        assert_eq!(error.column, 30);
+    }
+
+    /// Tests transaction control statements.
+    #[test]
+    fn test_transaction_statements() {
+        let mut parser = Parser::new(
+            "BEGIN; BEGIN IMMEDIATE TRANSACTION; SAVEPOINT sp1; RELEASE SAVEPOINT sp1; \
+             ROLLBACK TO sp1; ROLLBACK TRANSACTION; COMMIT; END TRANSACTION",
+        )
+        .unwrap();
+        let queries = parser.parse_statements().unwrap();
+        assert_eq!(queries.len(), 8);
+        assert!(matches!(
+            queries[0],
+            Query::Begin(TransactionMode::Deferred)
+        ));
+        assert!(matches!(
+            queries[1],
+            Query::Begin(TransactionMode::Immediate)
+        ));
+        assert!(matches!(queries[2], Query::Savepoint(ref name) if name == "sp1"));
+        assert!(matches!(queries[3], Query::Release(ref name) if name == "sp1"));
+        assert!(
+            matches!(queries[4], Query::Rollback { savepoint: Some(ref name) } if name == "sp1")
+        );
+        assert!(matches!(queries[5], Query::Rollback { savepoint: None }));
+        assert!(matches!(queries[6], Query::Commit));
+        assert!(matches!(queries[7], Query::Commit));
+    }
+
+    /// Tests the three forms of PRAGMA.
+    #[test]
+    fn test_pragma() {
+        let Query::Pragma(pragma) = parse("PRAGMA main.journal_mode") else {
+            panic!("Expected a PRAGMA");
+        };
+        assert_eq!(pragma.schema.as_deref(), Some("main"));
+        assert_eq!(pragma.name, "journal_mode");
+        assert!(pragma.value.is_none());
+
+        let Query::Pragma(pragma) = parse("PRAGMA foreign_keys = ON") else {
+            panic!("Expected a PRAGMA");
+        };
+        assert!(matches!(pragma.value, Some(Value::Text(ref word)) if word == "ON"));
+
+        let Query::Pragma(pragma) = parse("PRAGMA cache_size(-2000)") else {
+            panic!("Expected a PRAGMA");
+        };
+        assert!(matches!(pragma.value, Some(Value::Integer(-2000))));
+    }
+
+    /// Tests EXPLAIN and EXPLAIN QUERY PLAN prefixes.
+    #[test]
+    fn test_explain() {
+        let Query::Explain {
+            query_plan,
+            statement,
+        } = parse("EXPLAIN QUERY PLAN SELECT * FROM users WHERE id = 1")
+        else {
+            panic!("Expected EXPLAIN");
+        };
+        assert!(query_plan);
+        assert!(matches!(*statement, Query::Select(_)));
+
+        let Query::Explain {
+            query_plan,
+            statement,
+        } = parse("EXPLAIN DELETE FROM users")
+        else {
+            panic!("Expected EXPLAIN");
+        };
+        assert!(!query_plan);
+        assert!(matches!(*statement, Query::Delete(_)));
+        assert!(Parser::new("EXPLAIN EXPLAIN SELECT a FROM t")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests CREATE TRIGGER with NEW./OLD. references in WHEN and the body.
+    #[test]
+    fn test_create_trigger() {
+        let Query::CreateTrigger(trigger) = parse(
+            "CREATE TRIGGER IF NOT EXISTS log_rename AFTER UPDATE OF name ON users \
+             FOR EACH ROW WHEN old.name <> new.name BEGIN \
+             INSERT INTO audit (user_id, old_name) VALUES (new.id, old.name); \
+             UPDATE users SET renamed = TRUE WHERE id = new.id; \
+             END",
+        ) else {
+            panic!("Expected a CREATE TRIGGER query");
+        };
+        assert_eq!(trigger.name, "log_rename");
+        assert!(trigger.if_not_exists && trigger.for_each_row);
+        assert_eq!(trigger.timing, TriggerTiming::After);
+        assert!(matches!(trigger.event, TriggerEvent::Update(ref columns) if columns == &["name"]));
+        assert_eq!(trigger.table, "users");
+        let Some(Expression::Binary { ref left, .. }) = trigger.when else {
+            panic!("Expected a WHEN condition");
+        };
+        assert!(matches!(**left, Expression::Identifier(ref name) if name == "old.name"));
+        assert_eq!(trigger.body.len(), 2);
+        let Query::Insert(ref insert) = trigger.body[0] else {
+            panic!("Expected an INSERT in the trigger body");
+        };
+        let row = &insert.values.as_ref().unwrap()[0];
+        assert!(matches!(row[0], Expression::Identifier(ref name) if name == "new.id"));
+        assert!(matches!(trigger.body[1], Query::Update(_)));
+
+        let Query::CreateTrigger(trigger) =
+            parse("CREATE TEMP TRIGGER t INSTEAD OF DELETE ON v BEGIN DELETE FROM users; END")
+        else {
+            panic!("Expected a CREATE TRIGGER query");
+        };
+        assert!(trigger.temporary && !trigger.for_each_row);
+        assert_eq!(trigger.timing, TriggerTiming::InsteadOf);
+        assert!(Parser::new("CREATE TRIGGER t DELETE ON v BEGIN END")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    /// Tests column constraints in CREATE TABLE.
+    #[test]
+    fn test_column_constraints() {
+        let Query::CreateTable(create) = parse(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY AUTOINCREMENT, \
+             user_id INTEGER NOT NULL REFERENCES users (id), \
+             code TEXT UNIQUE COLLATE nocase, \
+             quantity INTEGER DEFAULT -1 CHECK (quantity <> 0), \
+             note TEXT, \
+             label TEXT DEFAULT -1 COLLATE nocase)",
+        ) else {
+            panic!("Expected a CREATE TABLE query");
+        };
+        let constraints: Vec<_> = create.columns.iter().map(|c| &c.constraints).collect();
+        assert!(matches!(
+            constraints[0][..],
+            [ColumnConstraint::PrimaryKey {
+                direction: SortOrder::Ascending,
+                autoincrement: true
+            }]
+        ));
+        assert!(matches!(constraints[1][0], ColumnConstraint::NotNull));
+        assert!(
+            matches!(constraints[1][1], ColumnConstraint::References { ref table, ref columns } if table == "users" && columns == &["id"])
+        );
+        assert!(matches!(constraints[2][0], ColumnConstraint::Unique));
+        assert!(
+            matches!(constraints[2][1], ColumnConstraint::Collate(ref name) if name == "NOCASE")
+        );
+        assert!(matches!(
+            constraints[3][0],
+            ColumnConstraint::Default(Expression::Unary { .. })
+        ));
+        assert!(matches!(constraints[3][1], ColumnConstraint::Check(_)));
+        assert!(constraints[4].is_empty());
+        assert!(matches!(
+            constraints[5][..],
+            [
+                ColumnConstraint::Default(Expression::Unary { .. }),
+                ColumnConstraint::Collate(_)
+            ]
+        ));
+    }
+
+    /// Tests that a single statement may be followed only by a `;`.
+    #[test]
+    fn test_parse_rejects_trailing_input() {
+        let limits = Limits::default();
+        assert!(parse_with_limits("SELECT a FROM t;", limits).is_ok());
+        let error = parse_with_limits("SELECT a FROM t; DROP TABLE t", limits).unwrap_err();
+        assert_eq!(error.found.as_deref(), Some("DROP"));
+        assert_eq!(error.column, 18);
+        for sql in ["SELECT a FROM t garbage more", "SELECT a FROM t ORDER x"] {
+            assert!(parse_with_limits(sql, limits).is_err(), "{}", sql);
+            assert!(Parser::new(sql).unwrap().parse().is_err(), "{}", sql);
+        }
+    }
+
+    /// Tests that malformed tokens are reported with their message and
+    /// position rather than as the end of the input.
+    #[test]
+    fn test_malformed_token_errors() {
+        let cases = [
+            (
+                "SELECT a FROM t \"abc",
+                "Unterminated quoted identifier.",
+                17,
+            ),
+            (
+                "SELECT a FROM t WHERE b = 'abc",
+                "Unterminated string literal.",
+                27,
+            ),
+            (
+                "SELECT X'ABC' FROM t",
+                "Odd number of hex digits in blob.",
+                8,
+            ),
+            (
+                "SELECT 99999999999999999999 FROM t",
+                "Integer literal out of range.",
+                8,
+            ),
+        ];
+        for (sql, message, column) in cases {
+            let error = Parser::new(sql).unwrap().parse().unwrap_err();
+            assert_eq!(error.message, message, "{}", sql);
+            assert_eq!(error.column, column, "{}", sql);
+            assert!(error.found.is_some(), "{}", sql);
+        }
+    }
+
+    /// Tests that non-reserved keywords still work as column, table and
+    /// function names, while reserved ones do not.
+    #[test]
+    fn test_non_reserved_keywords_as_names() {
+        let query = parse("SELECT after, temp, end, t.index, left(a, 2) FROM index AS left");
+        let Query::Select(select) = query else {
+            panic!("Expected a SELECT query");
+        };
+        let names: Vec<String> = select
+            .columns
+            .iter()
+            .map(|item| match &item.expression {
+                Expression::Identifier(name) => name.clone(),
+                Expression::Function { name, .. } => format!("{}()", name),
+                other => panic!("Unexpected expression {:?}", other),
+            })
+            .collect();
+        assert_eq!(names, ["after", "temp", "end", "t.index", "left()"]);
+        assert_eq!(select.table.scope_name(), Some("left"));
+
+        assert!(parse_with_limits("CREATE TABLE if (a)", Limits::default()).is_ok());
+        assert!(parse_with_limits("SELECT select FROM t", Limits::default()).is_err());
+    }
 }
@@ -0,0 +1,165 @@
+use crate::ast::Query;
+use crate::dialect::Dialect;
+use crate::parser::Parser;
+use std::io::{BufRead, BufReader, Read};
+
+/// Reads `;`-separated statements from a `Read` source and parses them one
+/// at a time, so a large dump never has to be held in memory as a whole.
+/// Only the statement currently being parsed is buffered.
+pub struct StatementReader<R: Read> {
+    reader: BufReader<R>,
+    buffer: Vec<u8>,
+    dialect: Dialect,
+    done: bool,
+}
+
+impl<R: Read> StatementReader<R> {
+    pub fn new(reader: R) -> Self {
+        StatementReader::with_dialect(reader, Dialect::default())
+    }
+
+    /// Creates a reader for statements written for `dialect`, which decides
+    /// how identifiers are quoted and whether `\` escapes in strings.
+    pub fn with_dialect(reader: R, dialect: Dialect) -> Self {
+        StatementReader {
+            reader: BufReader::new(reader),
+            buffer: Vec::new(),
+            dialect,
+            done: false,
+        }
+    }
+
+    /// Reads the text of the next statement, without its terminating `;`.
+    /// A `;` inside a string literal, a quoted identifier or a comment does
+    /// not end the statement.
+    fn read_statement(&mut self) -> Result<Option<String>, String> {
+        self.buffer.clear();
+        let mut state = Scan::Code;
+        loop {
+            let start = self.buffer.len();
+            let read = self
+                .reader
+                .read_until(b';', &mut self.buffer)
+                .map_err(|e| format!("Failed to read a statement: {}", e))?;
+            if read == 0 {
+                self.done = true;
+                break;
+            }
+
+            // Chunks end just after a `;`, so a two-byte comment marker or
+            // an escape is never split between them.
+            let chunk = &self.buffer[start..];
+            let mut i = 0;
+            while i < chunk.len() {
+                let next = chunk.get(i + 1).copied();
+                state = match (state, chunk[i], next) {
+                    (Scan::Code, b'\'', _) => Scan::String,
+                    (Scan::Code, b'-', Some(b'-')) => Scan::LineComment,
+                    (Scan::Code, b'#', _) if self.dialect.hash_comments() => Scan::LineComment,
+                    (Scan::Code, b'/', Some(b'*')) => {
+                        i += 1;
+                        Scan::BlockComment
+                    }
+                    (Scan::Code, c, _) => match self.dialect.identifier_quote(c as char) {
+                        Some(close) => Scan::Identifier(close as u8),
+                        None => Scan::Code,
+                    },
+                    (Scan::String, b'\\', _) if self.dialect.backslash_escapes() => {
+                        i += 1;
+                        Scan::String
+                    }
+                    // A doubled quote leaves and re-enters the literal or
+                    // identifier
+                    (Scan::String, b'\'', _) => Scan::Code,
+                    (Scan::Identifier(close), c, _) if c == close => Scan::Code,
+                    (Scan::LineComment, b'\n', _) => Scan::Code,
+                    (Scan::BlockComment, b'*', Some(b'/')) => {
+                        i += 1;
+                        Scan::Code
+                    }
+                    (state, _, _) => state,
+                };
+                i += 1;
+            }
+            if state == Scan::Code && self.buffer.last() == Some(&b';') {
+                self.buffer.pop();
+                break;
+            }
+        }
+
+        let statement = String::from_utf8(std::mem::take(&mut self.buffer))
+            .map_err(|_| "The statement is not valid UTF-8.".to_string())?;
+        if statement.trim().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(statement))
+        }
+    }
+}
+
+/// Where the reader is within a statement's text.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    Code,
+    String,
+    /// Inside a quoted identifier that ends with the given byte.
+    Identifier(u8),
+    LineComment,
+    BlockComment,
+}
+
+impl<R: Read> Iterator for StatementReader<R> {
+    type Item = Result<Query, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read_statement() {
+                Ok(Some(statement)) => {
+                    let query = Parser::with_dialect(&statement, self.dialect)
+                        .and_then(|mut parser| parser.parse());
+                    return Some(query.map_err(|e| e.to_string()));
+                }
+                Ok(None) => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that statements are split on `;` outside literals, quoted
+    /// identifiers and comments.
+    #[test]
+    fn test_statement_reader() {
+        let input = "INSERT INTO users (name) VALUES ('a;b');\n\
+                     -- a comment; not a statement\n\
+                     INSERT INTO users (name) /* ; */ VALUES ('it''s; c') ;;\n\
+                     DELETE FROM users";
+        let queries: Vec<_> = StatementReader::new(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(queries.len(), 3);
+        assert!(matches!(queries[0], Query::Insert(_)));
+        assert!(matches!(queries[1], Query::Insert(_)));
+        assert!(matches!(queries[2], Query::Delete(_)));
+
+        let input = "SELECT \"a;b\" FROM [t;1]; DELETE FROM t";
+        let queries: Vec<_> = StatementReader::new(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(queries.len(), 2);
+
+        let input = "SELECT `a;b` FROM t WHERE c = 'it\\'s; d'; # e; f\n DELETE FROM t";
+        let queries: Vec<_> = StatementReader::with_dialect(input.as_bytes(), Dialect::MySql)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(queries.len(), 2);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::RwLock;
@@ -63,6 +64,10 @@ impl Page {
 /// StorageEngine manages reading and writing pages to disk.
 pub struct StorageEngine {
     file: File,
+    file_path: String,
+    /// Pages written by a branch. A branch reads its parent's file for every
+    /// page it has not written itself and never writes to that file.
+    overlay: Option<HashMap<u32, Vec<u8>>>,
 }
 
 impl StorageEngine {
@@ -73,24 +78,48 @@ impl StorageEngine {
             .write(true)
             .create(true)
             .open(file_path)?;
-        Ok(StorageEngine { file })
+        Ok(StorageEngine {
+            file,
+            file_path: file_path.to_string(),
+            overlay: None,
+        })
+    }
+
+    /// Creates a copy-on-write branch of this database. The branch shares
+    /// every page already on disk and keeps the pages it writes in memory,
+    /// so its changes are discarded when it is dropped. Pages this engine
+    /// writes to disk after branching are visible to the branch unless the
+    /// branch has written them too.
+    pub fn branch(&self) -> std::io::Result<Self> {
+        let file = File::open(&self.file_path)?;
+        Ok(StorageEngine {
+            file,
+            file_path: self.file_path.clone(),
+            overlay: Some(self.overlay.clone().unwrap_or_default()),
+        })
     }
 
     /// Reads a page from disk by its ID.
     pub fn read_page(&mut self, page_id: u32) -> std::io::Result<PageData> {
+        if let Some(buffer) = self.overlay.as_ref().and_then(|o| o.get(&page_id)) {
+            return Self::decode_page(buffer);
+        }
+
         let mut buffer = vec![0u8; PAGE_SIZE];
         self.file
             .seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))?;
         self.file.read_exact(&mut buffer)?;
+        Self::decode_page(&buffer)
+    }
 
-        // Deserialize the page data
-        let page_data: PageData = bincode::deserialize(&buffer)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        Ok(page_data)
+    /// Deserializes the page data stored in a page-sized buffer.
+    fn decode_page(buffer: &[u8]) -> std::io::Result<PageData> {
+        bincode::deserialize(buffer)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
-    /// Writes a page to disk.
-    pub fn write_page(&mut self, page_data: &PageData) -> std::io::Result<()> {
+    /// Serializes page data into a page-sized buffer, zero-filling the rest.
+    fn encode_page(page_data: &PageData) -> std::io::Result<Vec<u8>> {
         // Serialize the page data
         let encoded: Vec<u8> = bincode::serialize(page_data)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
@@ -105,6 +134,17 @@ impl StorageEngine {
         // Pad the buffer to PAGE_SIZE
         let mut buffer = encoded;
         buffer.resize(PAGE_SIZE, 0u8);
+        Ok(buffer)
+    }
+
+    /// Writes a page to disk.
+    pub fn write_page(&mut self, page_data: &PageData) -> std::io::Result<()> {
+        let buffer = Self::encode_page(page_data)?;
+
+        if let Some(overlay) = self.overlay.as_mut() {
+            overlay.insert(page_data.id, buffer);
+            return Ok(());
+        }
 
         self.file
             .seek(SeekFrom::Start(page_data.id as u64 * PAGE_SIZE as u64))?;
@@ -112,11 +152,106 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Returns the number of pages, including those only in a branch.
+    pub fn page_count(&self) -> std::io::Result<u32> {
+        let mut count = (self.file.metadata()?.len() / PAGE_SIZE as u64) as u32;
+        if let Some(last) = self.overlay.as_ref().and_then(|o| o.keys().max()) {
+            count = count.max(last + 1);
+        }
+        Ok(count)
+    }
+
     /// Allocates a new page with the specified node type.
     pub fn allocate_page(&mut self, node_type: NodeType) -> std::io::Result<PageData> {
-        let page_id = (self.file.metadata()?.len() / PAGE_SIZE as u64) as u32;
+        let page_id = self.page_count()?;
         let page_data = PageData::new(page_id, node_type);
         self.write_page(&page_data)?;
         Ok(page_data)
     }
+
+    /// Writes a deterministic image of the database: every page in page ID
+    /// order, re-encoded from its data so that the unused end of each page
+    /// is zeroed. Databases with the same pages produce identical images,
+    /// whatever bytes their files hold past each page's data and whether or
+    /// not they are branches.
+    pub fn write_image<W: Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        for page_id in 0..self.page_count()? {
+            let page_data = self.read_page(page_id)?;
+            writer.write_all(&Self::encode_page(&page_data)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Tests that a branch sees its parent's pages but keeps its own writes.
+    #[test]
+    fn test_branch_copy_on_write() {
+        let test_db = "test_branch.db";
+        let _ = fs::remove_file(test_db);
+
+        let mut parent = StorageEngine::new(test_db).unwrap();
+        let mut page = parent.allocate_page(NodeType::Leaf).unwrap();
+        page.keys = vec![1, 2, 3];
+        parent.write_page(&page).unwrap();
+
+        let mut branch = parent.branch().unwrap();
+        assert_eq!(branch.read_page(0).unwrap().keys, vec![1, 2, 3]);
+
+        page.keys = vec![42];
+        branch.write_page(&page).unwrap();
+        let new_page = branch.allocate_page(NodeType::Internal).unwrap();
+        assert_eq!(new_page.id, 1);
+
+        // The branch sees its own writes; the parent and its file do not
+        assert_eq!(branch.read_page(0).unwrap().keys, vec![42]);
+        assert_eq!(parent.read_page(0).unwrap().keys, vec![1, 2, 3]);
+        assert!(parent.read_page(1).is_err());
+        assert_eq!(fs::metadata(test_db).unwrap().len(), PAGE_SIZE as u64);
+
+        let _ = fs::remove_file(test_db);
+    }
+
+    /// Tests that images depend only on page contents.
+    #[test]
+    fn test_deterministic_image() {
+        let test_db = "test_image.db";
+        let other_db = "test_image_other.db";
+        let _ = fs::remove_file(test_db);
+        let _ = fs::remove_file(other_db);
+
+        let mut engine = StorageEngine::new(test_db).unwrap();
+        let mut page = engine.allocate_page(NodeType::Leaf).unwrap();
+        page.keys = vec![1, 2, 3];
+        engine.write_page(&page).unwrap();
+
+        // Leave stray bytes past the page data, as a torn write might
+        let mut file = OpenOptions::new().write(true).open(test_db).unwrap();
+        file.seek(SeekFrom::Start(PAGE_SIZE as u64 - 4)).unwrap();
+        file.write_all(&[0xff; 4]).unwrap();
+
+        // The same page written through a branch of an empty database
+        let mut other = StorageEngine::new(other_db).unwrap();
+        let mut branch = other.branch().unwrap();
+        branch.write_page(&page).unwrap();
+
+        let mut image = Vec::new();
+        engine.write_image(&mut image).unwrap();
+        let mut branch_image = Vec::new();
+        branch.write_image(&mut branch_image).unwrap();
+        assert_eq!(image.len(), PAGE_SIZE);
+        assert_eq!(image, branch_image);
+        assert!(image[PAGE_SIZE - 4..].iter().all(|&b| b == 0));
+
+        let mut other_image = Vec::new();
+        other.write_image(&mut other_image).unwrap();
+        assert!(other_image.is_empty());
+
+        let _ = fs::remove_file(test_db);
+        let _ = fs::remove_file(other_db);
+    }
 }
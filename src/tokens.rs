@@ -1,10 +1,16 @@
+use std::borrow::Cow;
+
+/// A lexical token. Text payloads borrow from the SQL input where possible;
+/// `into_owned` detaches a token from the input.
 #[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+pub enum Token<'a> {
     Asterisk,
-    Identifier(String),
+    Identifier(Cow<'a, str>),
     Integer(i64),
     Float(f64),
-    StringLiteral(String),
+    StringLiteral(Cow<'a, str>),
+    /// A blob literal `X'ABCD'`, decoded to its bytes.
+    Blob(Vec<u8>),
     Boolean(bool),
     Null,
     Equal,
@@ -13,34 +19,321 @@ pub enum Token {
     LessThanOrEqual,
     GreaterThan,
     GreaterThanOrEqual,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
     Comma,
+    Semicolon,
     LeftParen,
     RightParen,
     Dot,
-    Keyword(String),
-}
-
-pub fn is_keyword(literal: &str) -> bool {
-    matches!(
-        literal.to_uppercase().as_str(),
-        "SELECT"
-            | "INSERT"
-            | "INTO"
-            | "VALUES"
-            | "FROM"
-            | "JOIN"
-            | "ON"
-            | "WHERE"
-            | "GROUP"
-            | "BY"
-            | "HAVING"
-            | "ORDER"
-            | "ASC"
-            | "DESC"
-            | "AND"
-            | "OR"
-            | "NOT"
-    )
+    Keyword(Keyword),
+    /// A session variable reference `@name`, without the `@`.
+    Variable(Cow<'a, str>),
+    /// A bind parameter as written: `?`, `?NNN`, `:name` or `$name`.
+    Parameter(Cow<'a, str>),
+    /// A character that does not start any token. The lexer carries on past
+    /// it, so the parser can report it with its position.
+    Unknown(char),
+    /// A malformed token, such as an unterminated string, with a message
+    /// saying what is wrong. Like `Unknown`, it is reported by the parser.
+    Error(&'static str),
+}
+
+impl Token<'_> {
+    /// Copies any borrowed text so the token no longer refers to the input.
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::Asterisk => Token::Asterisk,
+            Token::Identifier(s) => Token::Identifier(Cow::Owned(s.into_owned())),
+            Token::Integer(i) => Token::Integer(i),
+            Token::Float(f) => Token::Float(f),
+            Token::StringLiteral(s) => Token::StringLiteral(Cow::Owned(s.into_owned())),
+            Token::Blob(b) => Token::Blob(b),
+            Token::Boolean(b) => Token::Boolean(b),
+            Token::Null => Token::Null,
+            Token::Equal => Token::Equal,
+            Token::NotEqual => Token::NotEqual,
+            Token::LessThan => Token::LessThan,
+            Token::LessThanOrEqual => Token::LessThanOrEqual,
+            Token::GreaterThan => Token::GreaterThan,
+            Token::GreaterThanOrEqual => Token::GreaterThanOrEqual,
+            Token::Plus => Token::Plus,
+            Token::Minus => Token::Minus,
+            Token::Slash => Token::Slash,
+            Token::Percent => Token::Percent,
+            Token::Comma => Token::Comma,
+            Token::Semicolon => Token::Semicolon,
+            Token::LeftParen => Token::LeftParen,
+            Token::RightParen => Token::RightParen,
+            Token::Dot => Token::Dot,
+            Token::Keyword(k) => Token::Keyword(k),
+            Token::Variable(s) => Token::Variable(Cow::Owned(s.into_owned())),
+            Token::Parameter(s) => Token::Parameter(Cow::Owned(s.into_owned())),
+            Token::Unknown(c) => Token::Unknown(c),
+            Token::Error(message) => Token::Error(message),
+        }
+    }
+}
+
+impl std::fmt::Display for Token<'_> {
+    /// Writes the token as it would appear in SQL.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Asterisk => write!(f, "*"),
+            Token::Identifier(s) => write!(f, "{}", s),
+            Token::Integer(i) => write!(f, "{}", i),
+            Token::Float(x) => write!(f, "{}", x),
+            Token::StringLiteral(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Token::Blob(bytes) => {
+                write!(f, "X'")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+                write!(f, "'")
+            }
+            Token::Boolean(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+            Token::Null => write!(f, "NULL"),
+            Token::Equal => write!(f, "="),
+            Token::NotEqual => write!(f, "!="),
+            Token::LessThan => write!(f, "<"),
+            Token::LessThanOrEqual => write!(f, "<="),
+            Token::GreaterThan => write!(f, ">"),
+            Token::GreaterThanOrEqual => write!(f, ">="),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Dot => write!(f, "."),
+            Token::Keyword(k) => write!(f, "{}", k),
+            Token::Variable(s) => write!(f, "@{}", s),
+            Token::Parameter(s) => write!(f, "{}", s),
+            Token::Unknown(c) => write!(f, "{}", c),
+            Token::Error(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Declares the `Keyword` enum together with the spelling of each keyword,
+/// so the two cannot drift apart. Reserved keywords are never names;
+/// non-reserved ones are read as names wherever the grammar expects one.
+macro_rules! keywords {
+    (
+        reserved { $($reserved:ident => $reserved_text:literal,)* }
+        non_reserved { $($other:ident => $other_text:literal,)* }
+    ) => {
+        /// A keyword. The lexer resolves keywords once, so the parser
+        /// compares enum values rather than strings.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Keyword {
+            $($reserved,)*
+            $($other,)*
+        }
+
+        impl Keyword {
+            /// Every keyword, reserved ones first.
+            const ALL: &'static [Keyword] = &[
+                $(Keyword::$reserved,)*
+                $(Keyword::$other,)*
+            ];
+
+            /// The keyword as it is written in SQL, upper-cased.
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $(Keyword::$reserved => $reserved_text,)*
+                    $(Keyword::$other => $other_text,)*
+                }
+            }
+
+            /// Whether the keyword can never be used as a name. A
+            /// non-reserved keyword such as `INDEX` or `LEFT` can still name
+            /// a column, a table or a function.
+            pub fn is_reserved(self) -> bool {
+                matches!(self, $(Keyword::$reserved)|*)
+            }
+        }
+    };
+}
+
+impl Keyword {
+    /// Resolves a word to a keyword, ignoring case. The word is
+    /// upper-cased into a stack buffer, so nothing is allocated, and then
+    /// looked up in a perfect hash table, so at most one keyword is
+    /// compared against it.
+    pub fn lookup(word: &str) -> Option<Keyword> {
+        let mut buffer = [0u8; MAX_KEYWORD_LEN];
+        let upper = buffer.get_mut(..word.len())?;
+        upper.copy_from_slice(word.as_bytes());
+        upper.make_ascii_uppercase();
+        let (seed, table) = &KEYWORD_TABLE;
+        let keyword = table[keyword_slot(upper, *seed)]?;
+        (keyword.as_str().as_bytes() == upper).then_some(keyword)
+    }
+}
+
+/// The length of the longest keyword; longer words are never keywords.
+const MAX_KEYWORD_LEN: usize = 17;
+
+/// The number of slots in the keyword table. It is a power of two about
+/// twenty times the number of keywords, so a seed that gives every keyword
+/// its own slot turns up after a handful of tries.
+const KEYWORD_SLOTS: usize = 2048;
+
+/// The keyword table: a seed, and the slots the seeded hash sends each
+/// keyword to. The compiler builds it, so adding a keyword needs no
+/// generated code.
+static KEYWORD_TABLE: (u32, [Option<Keyword>; KEYWORD_SLOTS]) = build_keyword_table();
+
+/// Hashes an upper-cased word to a slot with FNV-1a, starting from `seed`.
+const fn keyword_slot(word: &[u8], seed: u32) -> usize {
+    let mut hash = 0x811c_9dc5 ^ seed;
+    let mut i = 0;
+    while i < word.len() {
+        hash ^= word[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash as usize % KEYWORD_SLOTS
+}
+
+/// Tries seeds in turn until no two keywords share a slot.
+const fn build_keyword_table() -> (u32, [Option<Keyword>; KEYWORD_SLOTS]) {
+    let mut seed = 0;
+    'seeds: loop {
+        let mut table = [None; KEYWORD_SLOTS];
+        let mut i = 0;
+        while i < Keyword::ALL.len() {
+            let keyword = Keyword::ALL[i];
+            let slot = keyword_slot(keyword.as_str().as_bytes(), seed);
+            if table[slot].is_some() {
+                seed += 1;
+                continue 'seeds;
+            }
+            table[slot] = Some(keyword);
+            i += 1;
+        }
+        return (seed, table);
+    }
+}
+
+keywords! {
+    reserved {
+        Select => "SELECT",
+        Insert => "INSERT",
+        Into => "INTO",
+        Values => "VALUES",
+        From => "FROM",
+        Join => "JOIN",
+        On => "ON",
+        Where => "WHERE",
+        Group => "GROUP",
+        Having => "HAVING",
+        Order => "ORDER",
+        And => "AND",
+        Or => "OR",
+        Not => "NOT",
+        As => "AS",
+        Table => "TABLE",
+        Using => "USING",
+        When => "WHEN",
+        Then => "THEN",
+        Update => "UPDATE",
+        Set => "SET",
+        Delete => "DELETE",
+        Is => "IS",
+        Distinct => "DISTINCT",
+        Collate => "COLLATE",
+        Create => "CREATE",
+        Drop => "DROP",
+        Exists => "EXISTS",
+        To => "TO",
+        All => "ALL",
+        Alter => "ALTER",
+        Add => "ADD",
+        Unique => "UNIQUE",
+        Default => "DEFAULT",
+        Indexed => "INDEXED",
+        Limit => "LIMIT",
+        In => "IN",
+        Case => "CASE",
+        Else => "ELSE",
+        Between => "BETWEEN",
+        Escape => "ESCAPE",
+        Union => "UNION",
+        Intersect => "INTERSECT",
+        Except => "EXCEPT",
+        Nothing => "NOTHING",
+        Returning => "RETURNING",
+        Commit => "COMMIT",
+        Primary => "PRIMARY",
+        Check => "CHECK",
+        References => "REFERENCES",
+        Autoincrement => "AUTOINCREMENT",
+    }
+    non_reserved {
+        By => "BY",
+        Asc => "ASC",
+        Desc => "DESC",
+        Truncate => "TRUNCATE",
+        Merge => "MERGE",
+        Matched => "MATCHED",
+        Nulls => "NULLS",
+        Temp => "TEMP",
+        Temporary => "TEMPORARY",
+        View => "VIEW",
+        If => "IF",
+        Of => "OF",
+        Grant => "GRANT",
+        Revoke => "REVOKE",
+        Privileges => "PRIVILEGES",
+        Materialized => "MATERIALIZED",
+        Refresh => "REFRESH",
+        CurrentTimestamp => "CURRENT_TIMESTAMP",
+        CurrentDate => "CURRENT_DATE",
+        CurrentTime => "CURRENT_TIME",
+        Rename => "RENAME",
+        Column => "COLUMN",
+        Index => "INDEX",
+        Inner => "INNER",
+        Left => "LEFT",
+        Right => "RIGHT",
+        Full => "FULL",
+        Outer => "OUTER",
+        Cross => "CROSS",
+        Natural => "NATURAL",
+        Offset => "OFFSET",
+        End => "END",
+        Like => "LIKE",
+        Cast => "CAST",
+        Tablesample => "TABLESAMPLE",
+        With => "WITH",
+        Recursive => "RECURSIVE",
+        Conflict => "CONFLICT",
+        Do => "DO",
+        Begin => "BEGIN",
+        Rollback => "ROLLBACK",
+        Savepoint => "SAVEPOINT",
+        Release => "RELEASE",
+        Pragma => "PRAGMA",
+        Explain => "EXPLAIN",
+        Trigger => "TRIGGER",
+        Before => "BEFORE",
+        After => "AFTER",
+        Instead => "INSTEAD",
+        For => "FOR",
+        Each => "EACH",
+    }
+}
+
+impl std::fmt::Display for Keyword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 pub fn is_boolean(literal: &str) -> bool {